@@ -0,0 +1,66 @@
+//! Throughput comparison of the allocating word counter against the memory mapped byte scanner.
+//! Run with `cargo bench --bench scan`, optionally followed by `-- <FILENAME>` to measure a specific file instead of generated text.
+
+use std::fs::{read_to_string, write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use wclib::scan;
+
+/// Size of the generated corpus when no file is provided.
+const GENERATED_BYTES: usize = 64 * 1024 * 1024;
+/// Number of timed runs per implementation, the fastest is reported.
+const RUNS: usize = 5;
+
+/// Generate mostly ASCII prose with punctuation, blank lines, CRLF endings and some multibyte characters using a fixed seed.
+fn generate_corpus(n_bytes: usize) -> String {
+    const WORDS: [&str; 16] = ["the", "quick", "brown", "fox", "jumps", "over", "lazy", "dog's", "tail", "--", "naïve", "café", "résumé", "42", "end.", "\u{a0}"];
+    let mut seed = 0x2545_F491_4F6C_DD1D_u64;
+    let mut corpus = String::with_capacity(n_bytes + 16);
+    while corpus.len() < n_bytes {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        corpus.push_str(WORDS[(seed % WORDS.len() as u64) as usize]);
+        corpus.push_str(match (seed >> 8) % 24 { 0 => "\n", 1 => "\r\n\r\n", _ => " " });
+    }
+    corpus
+}
+
+/// Time `f` over several runs, returning the fastest run in seconds along with its result.
+fn time_best<T>(mut f: impl FnMut() -> T) -> (f64, T) {
+    let mut best = f64::MAX;
+    let mut result = f();
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        result = f();
+        best = best.min(start.elapsed().as_secs_f64());
+    }
+    (best, result)
+}
+
+fn main() {
+    let path = std::env::args().skip(1).find(|a| !a.starts_with("--")).map(PathBuf::from).unwrap_or_else(|| {
+        let path = std::env::temp_dir().join("wc_scan_bench.txt");
+        write(&path, generate_corpus(GENERATED_BYTES)).expect("failed to write generated corpus");
+        path
+    });
+    let n_mib = std::fs::metadata(&path).expect("failed to read benchmark input").len() as f64 / (1024.0 * 1024.0);
+    println!("input: {:?} ({:.1} MiB), best of {} runs", path, n_mib, RUNS);
+
+    for is_include_all in [false, true] {
+        // Current implementation, reading into an owned String and building the frequency map
+        let (baseline_secs, baseline) = time_best(|| {
+            let contents = read_to_string(&path).expect("failed to read benchmark input");
+            let words: usize = wclib::count_words(&contents, false, is_include_all).values().sum();
+            (words, wclib::count_lines(&contents))
+        });
+        // Memory mapped input with the vectorized scanner
+        let (scan_secs, scanned) = time_best(|| scan::scan_file(&path, is_include_all).expect("failed to scan benchmark input"));
+        assert_eq!(baseline, (scanned.words, scanned.lines), "scanner totals disagree with the frequency map totals");
+
+        println!("include-all: {is_include_all}, {} words, {} lines", scanned.words, scanned.lines);
+        println!("  count_words + count_lines: {:>8.1} MiB/s", n_mib / baseline_secs);
+        println!("  scan_file (mmap + simd):   {:>8.1} MiB/s ({:.1}x)", n_mib / scan_secs, baseline_secs / scan_secs);
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{Table, TableStyle};
+
+/// Default number of counters tracked by `SpaceSaving` when no capacity is provided.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone)]
+/// A tracked word with its estimated count and the maximum amount by which that count may overestimate the true count.
+pub struct HeavyHitter {
+    pub word: String,
+    pub count: usize,
+    pub error: usize, // True count lies within `count - error ..= count`
+}
+
+#[derive(Debug, Clone)]
+/// Space-Saving heavy hitter summary that tracks at most `capacity` words, so memory stays bounded regardless of the number of distinct words in the input.
+/// Any word occurring more than `n / capacity` times in a stream of `n` words is guaranteed to be tracked.
+pub struct SpaceSaving {
+    capacity: usize,
+    n_items: usize, // Total number of words inserted
+    counters: Vec<HeavyHitter>,
+    index: HashMap<String, usize>, // Maps tracked words to their position in `counters`
+    by_count: BTreeSet<(usize, usize)>, // Ordered `(count, position)` pairs to find the minimum counter quickly
+}
+
+impl SpaceSaving {
+    /// Create an empty summary tracking at most `capacity` words, capacities of zero are treated as one.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        SpaceSaving { capacity, n_items: 0, counters: Vec::with_capacity(capacity), index: HashMap::with_capacity(capacity), by_count: BTreeSet::new() }
+    }
+    /// Returns the maximum number of words tracked at once.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    /// Returns the total number of words inserted into the summary.
+    pub fn n_items(&self) -> usize {
+        self.n_items
+    }
+    /// Returns the worst case overestimate of any reported count, which is `n / capacity`.
+    pub fn max_error(&self) -> usize {
+        self.n_items / self.capacity
+    }
+    /// Insert a single occurrence of `word`, replacing the least frequent tracked word if the summary is full.
+    pub fn insert(&mut self, word: &str) {
+        self.n_items += 1;
+        if let Some(&pos) = self.index.get(word) {
+            self.increment(pos);
+        } else if self.counters.len() < self.capacity {
+            let pos = self.counters.len();
+            self.counters.push(HeavyHitter { word: word.to_string(), count: 1, error: 0 });
+            self.index.insert(word.to_string(), pos);
+            self.by_count.insert((1, pos));
+        } else if let Some((min_count, pos)) = self.by_count.pop_first() {
+            // Evict the minimum counter, inheriting its count as the error bound of the new word
+            let counter = &mut self.counters[pos];
+            // Reuse the buffers of the evicted word, which only allocate if the new word is longer
+            let (mut key, _) = self.index.remove_entry(&counter.word).unwrap_or_default();
+            key.clear();
+            key.push_str(word);
+            counter.word.clear();
+            counter.word.push_str(word);
+            counter.count = min_count + 1;
+            counter.error = min_count;
+            self.index.insert(key, pos);
+            self.by_count.insert((min_count + 1, pos));
+        }
+    }
+    /// Increment the counter at `pos` while keeping the count ordering in sync.
+    fn increment(&mut self, pos: usize) {
+        let counter = &mut self.counters[pos];
+        self.by_count.remove(&(counter.count, pos));
+        counter.count += 1;
+        self.by_count.insert((counter.count, pos));
+    }
+    /// Returns the tracked words sorted by descending estimated count with ties broken alphabetically.
+    pub fn heavy_hitters(&self) -> Vec<&HeavyHitter> {
+        let mut hitters: Vec<&HeavyHitter> = self.counters.iter().collect();
+        hitters.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+        hitters
+    }
+}
+
+/// Create a frequency table of the top `top_n` heavy hitters with the error bound of each estimated count.
+pub fn generate_approx_table(summary: &SpaceSaving, top_n: usize, style: TableStyle) -> Table<String, String> {
+    let headers = vec!["#", "word", "ct", "±err"].into_iter().map(String::from).collect();
+    let data = summary.heavy_hitters().into_iter().take(top_n).enumerate()
+        .map(|(i, h)| vec![(i + 1).to_string(), h.word.clone(), h.count.to_string(), h.error.to_string()])
+        .collect();
+    Table::new_with_style(headers, data, style, "><>>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_overestimate_within_bound() {
+        let mut summary = SpaceSaving::new(10);
+        let mut exact: HashMap<String, usize> = HashMap::new();
+        // A skewed stream where word `i` occurs roughly 200 / (i + 1) times, with many more distinct words than counters
+        for i in 0..200_usize {
+            for j in 0..200 / (i + 1) {
+                let word = format!("w{}", (i * 7 + j) % 50);
+                summary.insert(&word);
+                *exact.entry(word).or_insert(0) += 1;
+            }
+        }
+        assert_eq!(summary.n_items(), exact.values().sum::<usize>());
+        for hitter in summary.heavy_hitters() {
+            let true_count = exact[&hitter.word];
+            assert!(hitter.count >= true_count, "{} underestimated", hitter.word);
+            assert!(hitter.count - hitter.error <= true_count, "{} error bound too small", hitter.word);
+            assert!(hitter.count - true_count <= summary.max_error(), "{} exceeds n / capacity", hitter.word);
+        }
+        // Every word occurring more than n / capacity times must be tracked
+        for (word, count) in exact.iter().filter(|(_, count)| **count > summary.max_error()) {
+            assert!(summary.heavy_hitters().iter().any(|h| &h.word == word), "{} occurring {} times is not tracked", word, count);
+        }
+    }
+
+    #[test]
+    fn exact_below_capacity() {
+        let mut summary = SpaceSaving::new(4);
+        for word in ["a", "b", "a", "c", "a", "b"] {
+            summary.insert(word);
+        }
+        let hitters: Vec<_> = summary.heavy_hitters().iter().map(|h| (h.word.as_str(), h.count, h.error)).collect();
+        assert_eq!(hitters, [("a", 3, 0), ("b", 2, 0), ("c", 1, 0)]);
+    }
+}
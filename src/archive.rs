@@ -0,0 +1,167 @@
+use std::io::{self, Cursor, Read};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines the archive formats whose members are counted individually without extracting them to disk.
+pub enum ArchiveKind {
+    Zip,
+    Tar
+}
+
+impl ArchiveKind {
+    /// Identifies the archive format from its magic bytes, the tar magic is found at offset 257 of the first header.
+    pub fn from_magic(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else if bytes.get(257..262) == Some(b"ustar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+    /// Returns the name of the archive format.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::Tar => "tar",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Include and exclude glob patterns selecting which archive members are counted.
+pub struct MemberFilter {
+    pub include: Vec<String>, // Members must match at least one of these patterns, all members match if empty
+    pub exclude: Vec<String>, // Members matching any of these patterns are skipped even if included
+}
+
+impl MemberFilter {
+    /// Returns true if the member path passes the include and exclude patterns.
+    pub fn is_match(&self, path: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| glob_match(p, path)))
+            && !self.exclude.iter().any(|p| glob_match(p, path))
+    }
+}
+
+/// Matches a path against a glob pattern where `*` and `?` match within a path segment and `**` matches across segments.
+/// Patterns without a `/` are matched against the file name only, so `*.md` selects Markdown files in any directory.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let path = if pattern.contains('/') { path } else { path.rsplit('/').next().unwrap_or(path) };
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    glob_match_chars(&pattern, &path)
+}
+
+fn glob_match_chars(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*', rest @ ..] => {
+            // `**/` also matches zero directories so `docs/**/*.md` selects `docs/a.md`
+            if let ['/', after @ ..] = rest {
+                if glob_match_chars(after, path) {
+                    return true
+                }
+            }
+            (0..=path.len()).any(|i| glob_match_chars(rest, &path[i..]))
+        },
+        ['*', rest @ ..] => (0..=path.len()).take_while(|i| *i == 0 || path[i - 1] != '/').any(|i| glob_match_chars(rest, &path[i..])),
+        ['?', rest @ ..] => path.first().is_some_and(|c| *c != '/') && glob_match_chars(rest, &path[1..]),
+        [c, rest @ ..] => path.first() == Some(c) && glob_match_chars(rest, &path[1..]),
+    }
+}
+
+/// Strips any leading `./` and `/` from a member path, so members of an archive made with `tar czf docs.tar.gz .` are matched
+/// and displayed as `docs/a.md` rather than `./docs/a.md`.
+fn normalize_member_path(path: &str) -> &str {
+    let mut path = path.trim_start_matches('/');
+    while let Some(rest) = path.strip_prefix("./") {
+        path = rest.trim_start_matches('/');
+    }
+    path
+}
+
+/// Reads each regular file member of the archive passing the filter in archive order, calling `f` with its normalized path and contents.
+pub fn for_each_member(bytes: &[u8], kind: ArchiveKind, filter: &MemberFilter, mut f: impl FnMut(&str, &[u8]) -> io::Result<()>) -> io::Result<()> {
+    let mut contents = Vec::new();
+    match kind {
+        ArchiveKind::Zip => {
+            let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+            for i in 0..archive.len() {
+                let mut member = archive.by_index(i)?;
+                let path = normalize_member_path(member.name()).to_string();
+                if !member.is_file() || !filter.is_match(&path) {
+                    continue;
+                }
+                contents.clear();
+                member.read_to_end(&mut contents)?;
+                f(&path, &contents)?;
+            }
+        },
+        ArchiveKind::Tar => {
+            let mut archive = tar::Archive::new(bytes);
+            for entry in archive.entries()? {
+                let mut member = entry?;
+                let path = normalize_member_path(&member.path()?.to_string_lossy()).to_string();
+                if !member.header().entry_type().is_file() || !filter.is_match(&path) {
+                    continue;
+                }
+                contents.clear();
+                member.read_to_end(&mut contents)?;
+                f(&path, &contents)?;
+            }
+        },
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds an uncompressed tar archive with the members written under the given paths as is.
+    fn tar_archive(members: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, contents) in members {
+            let mut header = tar::Header::new_ustar();
+            header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_size(contents.len() as u64);
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_cksum();
+            builder.append(&header, contents.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn glob_match_segments() {
+        assert!(glob_match("*.md", "docs/guide/a.md"));
+        assert!(!glob_match("docs/*.md", "docs/guide/a.md"));
+        assert!(glob_match("docs/**/*.md", "docs/a.md"));
+        assert!(glob_match("docs/**/*.md", "docs/guide/deep/a.md"));
+        assert!(glob_match("docs/**", "docs/guide/a.md"));
+        assert!(glob_match("docs/?.md", "docs/a.md"));
+        assert!(!glob_match("docs/?.md", "docs/ab.md"));
+        assert!(!glob_match("doc?/a.md", "doc//a.md"));
+        assert!(!glob_match("*.md", "a.mdx"));
+    }
+
+    #[test]
+    fn member_paths_are_normalized() {
+        assert_eq!(normalize_member_path("./docs/a.md"), "docs/a.md");
+        assert_eq!(normalize_member_path("././/docs/a.md"), "docs/a.md");
+        assert_eq!(normalize_member_path("/docs/a.md"), "docs/a.md");
+        assert_eq!(normalize_member_path("docs/./a.md"), "docs/./a.md");
+    }
+
+    #[test]
+    fn dot_prefixed_tar_members_match_filters() {
+        let bytes = tar_archive(&[("./docs/a.md", "alpha"), ("./top.md", "beta")]);
+        assert_eq!(ArchiveKind::from_magic(&bytes), Some(ArchiveKind::Tar));
+        let filter = MemberFilter { include: vec!["docs/**".to_string()], exclude: Vec::new() };
+        let mut members = Vec::new();
+        for_each_member(&bytes, ArchiveKind::Tar, &filter, |path, contents| {
+            members.push((path.to_string(), String::from_utf8_lossy(contents).into_owned()));
+            Ok(())
+        }).unwrap();
+        assert_eq!(members, vec![("docs/a.md".to_string(), "alpha".to_string())]);
+    }
+}
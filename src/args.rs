@@ -1,18 +1,73 @@
 use std::path::PathBuf;
 
-use clap::{value_parser, Arg, ArgAction, Command};
+use clap::{value_parser, Arg, ArgAction, ArgGroup, Command};
+use wclib::approx;
+use wclib::archive::MemberFilter;
+use wclib::chars::CharUnit;
+use wclib::code::{CodePart, Language};
+use wclib::compare::CompareSort;
+use wclib::encoding::{BinaryMode, Encoding};
+use wclib::entities::EntityKind;
+use wclib::histogram::HistogramKind;
+use wclib::hll;
+use wclib::limits::Limits;
+use wclib::lint::LintKind;
+use wclib::mail::{MailGroup, MailOptions};
+use wclib::markdown::MarkdownOptions;
+use wclib::markup::{MarkupKind, Selector};
+use wclib::structured::{DataField, JsonPath};
+use wclib::subtitle;
+use wclib::tfidf::{TfIdfLayout, TfIdfOptions};
 
 const VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
 
 #[derive(Debug)]
 pub struct WCArgs {
-    pub filename: PathBuf,
+    pub filenames: Vec<PathBuf>, // One or more files to count, frequencies are merged across all of them
+    pub encoding: Option<Encoding>, // Explicit input encoding, otherwise detected from a byte order mark falling back to UTF-8
+    pub binary_mode: BinaryMode, // How files detected as binary are handled
+    pub member_filter: MemberFilter, // Include and exclude patterns selecting which archive members are counted
+    pub markdown: Option<MarkdownOptions>, // Count only the prose of Markdown input, keeping the selected non-prose parts
+    pub markup: Option<MarkupKind>, // Count only the text content of HTML or XML input
+    pub selector: Option<Selector>, // Optional selector or path restricting HTML or XML counting to matching elements
+    pub code_part: Option<CodePart>, // Count only the comments, doc comments, strings or identifiers of source code input
+    pub code_language: Option<Language>, // Language of source code input, otherwise detected from the extension or shebang of each file
+    pub is_latex: bool, // Count only the text of LaTeX input and report the words per section
+    pub subtitle_max_wpm: Option<f64>, // Count only the spoken text of SRT or WebVTT input, flagging cues faster than this many words per minute
+    pub mail: Option<MailOptions>, // Count the body text of each email message of mbox or EML input, keeping the selected non-body parts
+    pub mail_group: Option<MailGroup>, // Aggregate email messages by sender or thread rather than counting each one
+    pub data_field: Option<DataField>, // Count only the values of a JSONPath or CSV column in structured data input, one record per value
+    pub counts_export: Option<PathBuf>, // Optional path to export the word and line counts of each file, member, chapter or record as CSV
     pub top_words: Option<usize>, // Optional field for top N word frequencies
     pub ignore_words: Option<Vec<String>>, // Optional words to ignore for frequency mapping
     pub is_cased: bool, // Do not ignore character casing when checking token equality for word frequency
     pub is_include_all: bool, // Include punctuation characters or other non [A-Z] characters in word mapping
     pub is_include_stopwords: bool, // Ignore stopwords like 'if', 'the', 'let', 'a', 'you', etc when counting word frequencies
     pub is_include_unique: bool, // Include unique word count in output summary
+    pub histogram: Option<HistogramKind>, // Optional histogram of word or line lengths to display
+    pub is_bars: bool, // Include proportional bar and percentage columns in the frequency table
+    pub entity_kinds: Option<Vec<EntityKind>>, // Kinds of entities like URLs and emails pulled out before tokenizing and listed in their own tables
+    pub char_unit: Option<CharUnit>, // Tally characters or grapheme clusters by general category and script alongside the word counts
+    pub lint_kinds: Option<Vec<LintKind>>, // Kinds of suspicious characters to report by position instead of counting, failing if any are found
+    pub spell_dictionary: Option<PathBuf>, // Hunspell `.dic` file to spell check words against instead of counting, failing if any are misspelled
+    pub personal_words: Option<PathBuf>, // Optional list of additional words accepted by the spell check, one per line
+    pub limits: Option<Limits>, // Word, line and sentence length limits and forbidden words to check each input against instead of counting
+    pub is_zipf: bool, // Include a Zipf's law rank-frequency analysis in the output
+    pub zipf_export: Option<PathBuf>, // Optional path to export rank-frequency pairs as CSV, implies `is_zipf`
+    pub compare_filename: Option<PathBuf>, // Optional second file to compare word frequencies against
+    pub compare_sort: CompareSort, // Ordering of the comparison table rows, either by absolute delta or keyness
+    pub tfidf_layout: Option<TfIdfLayout>, // Optional TF-IDF keyword ranking per file, either as separate tables or one combined table
+    pub tfidf_options: TfIdfOptions, // Smoothing and weighting options used for the TF-IDF ranking
+    pub approx_capacity: Option<usize>, // Number of words, not bytes, tracked for bounded memory approximate top N, enables approximate mode when provided
+    pub hll_precision: Option<u8>, // Precision of the HyperLogLog unique word estimate, enables streaming mode when provided
+}
+
+impl WCArgs {
+    /// Returns true if only word and line totals were requested, allowing the input to be scanned without building a frequency map.
+    pub fn is_totals_only(&self) -> bool {
+        self.top_words.unwrap_or_default() == 0 && !self.is_include_unique && self.histogram.is_none() && self.entity_kinds.is_none() && self.char_unit.is_none() && !self.is_zipf
+            && self.compare_filename.is_none() && self.tfidf_layout.is_none() && self.approx_capacity.is_none() && self.hll_precision.is_none()
+    }
 }
 
 pub fn parse_args() -> WCArgs {
@@ -23,12 +78,142 @@ pub fn parse_args() -> WCArgs {
         .after_help("For example, run `wc file.txt` to count the number of words in `file.txt`.")
         /* Positional arguments */
         .arg(Arg::new("file")
-            .help("The target file to open and count")
+            .help("The target file or files to open and count")
             .value_name("FILENAME")
             .required(true)
+            .num_args(1..)
             .value_parser(value_parser!(PathBuf))
-            .action(ArgAction::Set)
+            .action(ArgAction::Append)
             .index(1))        
+        /* Input decoding arguments */
+        .arg(Arg::new("encoding")
+            .long("encoding")
+            .short('e')
+            .value_name("ENCODING")
+            .value_parser(["utf-8", "latin1", "windows-1252", "utf-16le", "utf-16be"])
+            .help("Decodes input using the specified encoding instead of detecting a byte order mark or assuming UTF-8")
+            .action(ArgAction::Set))
+        .arg(Arg::new("binary-files")
+            .long("binary-files")
+            .value_name("warn|skip|text")
+            .value_parser(["warn", "skip", "text"])
+            .default_value("warn")
+            .help("Skips binary files with a warning, skips them silently or counts them as text")
+            .action(ArgAction::Set))
+        .arg(Arg::new("include")
+            .long("include")
+            .value_name("GLOB")
+            .help("Counts only archive members, or files of directories checked against limits, matching the pattern, may be repeated")
+            .action(ArgAction::Append))
+        .arg(Arg::new("exclude")
+            .long("exclude")
+            .value_name("GLOB")
+            .help("Skips archive members, or files of directories checked against limits, matching the pattern, may be repeated")
+            .action(ArgAction::Append))
+        .arg(Arg::new("markdown")
+            .long("markdown")
+            .alias("md")
+            .help("Parses input as CommonMark and counts only prose, skipping syntax, code, link targets, front matter and tables")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("markdown-include")
+            .long("markdown-include")
+            .value_name("code|links|front-matter|tables")
+            .value_parser(["code", "links", "front-matter", "tables"])
+            .value_delimiter(',')
+            .requires("markdown")
+            .help("Counts the listed non-prose parts of Markdown input as well")
+            .action(ArgAction::Append))
+        .arg(Arg::new("html")
+            .long("html")
+            .help("Counts only the text of HTML input, decoding entities and skipping tags, scripts and styles")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("xml")
+            .long("xml")
+            .help("Counts only the text of XML input, decoding entities and skipping tags")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("code")
+            .long("code")
+            .value_name("LANG")
+            .value_parser(["auto", "rust", "python", "javascript", "typescript", "c", "cpp", "go", "java", "shell"])
+            .help("Lexes input as source code in the language, or detects it from the extension with `auto`, counting only the selected part")
+            .action(ArgAction::Set))
+        .arg(Arg::new("code-part")
+            .long("code-part")
+            .value_name("comments|docs|strings|identifiers")
+            .value_parser(["comments", "docs", "strings", "identifiers"])
+            .default_value("comments")
+            .requires("code")
+            .help("Counts words in comments, doc comments, string literals or identifiers split on camelCase and snake_case")
+            .action(ArgAction::Set))
+        .arg(Arg::new("latex")
+            .long("latex")
+            .alias("tex")
+            .help("Counts only the text of LaTeX input, skipping the preamble, commands, math and comments, and reports words per section")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("subtitles")
+            .long("subtitles")
+            .alias("srt")
+            .alias("vtt")
+            .help("Counts only the spoken text of SRT or WebVTT subtitles, reporting duration and words per minute overall and per cue")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("max-wpm")
+            .long("max-wpm")
+            .value_name("WPM")
+            .value_parser(value_parser!(f64))
+            .requires("subtitles")
+            .help(format!("Reading speed in words per minute above which subtitle cues are flagged [default: {}]", subtitle::DEFAULT_MAX_WPM))
+            .action(ArgAction::Set))
+        .arg(Arg::new("mail")
+            .long("mail")
+            .alias("mbox")
+            .alias("eml")
+            .conflicts_with_all(["json-path", "csv-column"])
+            .help("Counts the body text of each message of mbox or EML input, decoding MIME parts and skipping headers, quoted replies and attachments")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("mail-include")
+            .long("mail-include")
+            .value_name("headers|quotes")
+            .value_parser(["headers", "quotes"])
+            .value_delimiter(',')
+            .requires("mail")
+            .help("Counts the headers or quoted reply blocks of email messages as well")
+            .action(ArgAction::Append))
+        .arg(Arg::new("mail-group")
+            .long("mail-group")
+            .value_name("sender|thread")
+            .value_parser(["sender", "thread"])
+            .requires("mail")
+            .help("Aggregates the counts of email messages by sender address or by thread instead of per message")
+            .action(ArgAction::Set))
+        .arg(Arg::new("json-path")
+            .long("json-path")
+            .value_name("PATH")
+            .value_parser(JsonPath::parse)
+            .conflicts_with("csv-column")
+            .help("Counts only values matching a JSONPath like `$.items[*].description` in JSON, JSON Lines or YAML input, one record per value")
+            .action(ArgAction::Set))
+        .arg(Arg::new("csv-column")
+            .long("csv-column")
+            .value_name("COLUMN")
+            .help("Counts only the values of a CSV or TSV column, by header name or 1-based position, one record per row")
+            .action(ArgAction::Set))
+        .arg(Arg::new("counts-export")
+            .long("counts-export")
+            .value_name("PATH")
+            .value_parser(value_parser!(PathBuf))
+            .help("Exports the words and lines of each file, archive member, chapter or data record as CSV to the provided path")
+            .action(ArgAction::Set))
+        .group(ArgGroup::new("format")
+            .args(["markdown", "html", "xml", "code", "latex", "subtitles", "mail"]))
+        .group(ArgGroup::new("markup")
+            .args(["html", "xml"]))
+        .arg(Arg::new("select")
+            .long("select")
+            .value_name("SELECTOR")
+            .value_parser(Selector::parse)
+            .requires("markup")
+            .help("Counts only elements matching a CSS selector like `article p.note` or a path like `/book//para`, requires --html or --xml")
+            .action(ArgAction::Set))
         /* Argument for top N word frequencies */
         .arg(Arg::new("top")
             .long("top")
@@ -79,9 +264,219 @@ pub fn parse_args() -> WCArgs {
             .alias("unique")
             .help("Includes unique word count in output summary")
             .action(ArgAction::SetTrue))                        
+        .arg(Arg::new("bars")
+            .long("bars")
+            .short('b')
+            .short_alias('B')
+            .alias("bar")
+            .help("Includes proportional bars and percentage of total in the frequency table")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("histogram")
+            .long("histogram")
+            .alias("hist")
+            .value_name("words|lines")
+            .value_parser(["words", "lines"])
+            .help("Displays a histogram of word or line lengths in characters")
+            .action(ArgAction::Set))
+        .arg(Arg::new("entities")
+            .long("entities")
+            .help("Pulls URLs, emails, @mentions, #hashtags, dates, numbers and emoji out before counting words and lists each kind in its own table")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("entity-kinds")
+            .long("entity-kinds")
+            .value_name("urls|emails|mentions|hashtags|dates|numbers|emoji")
+            .value_parser(EntityKind::ALL.map(|kind| kind.label()))
+            .value_delimiter(',')
+            .requires("entities")
+            .help("Recognizes only the listed kinds of entities, leaving the others to be counted as words")
+            .action(ArgAction::Append))
+        .arg(Arg::new("chars")
+            .long("chars")
+            .help("Tallies individual characters with their general category and script, with tables per category, script and punctuation mark")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("graphemes")
+            .long("graphemes")
+            .help("Tallies grapheme clusters instead of individual characters, implies --chars")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("lint-chars")
+            .long("lint-chars")
+            .conflicts_with_all(["top", "include-unique", "histogram", "entities", "chars", "graphemes", "zipf", "zipf-export", "compare", "tfidf", "approx", "hll", "counts-export"])
+            .help("Lists each invisible, bidirectional control, control, unusual space or confusable character with its line and column, exiting with status 1 if any are found")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("lint-kinds")
+            .long("lint-kinds")
+            .value_name("invisible|bidi|controls|spaces|confusables")
+            .value_parser(LintKind::ALL.map(|kind| kind.label()))
+            .value_delimiter(',')
+            .requires("lint-chars")
+            .help("Reports only the listed kinds of suspicious characters")
+            .action(ArgAction::Append))
+        .arg(Arg::new("spellcheck")
+            .long("spellcheck")
+            .value_name("DIC")
+            .value_parser(value_parser!(PathBuf))
+            .conflicts_with_all(["top", "include-unique", "histogram", "entities", "chars", "graphemes", "lint-chars", "zipf", "zipf-export", "compare", "tfidf", "approx", "hll", "counts-export"])
+            .help("Lists words missing from a Hunspell dictionary and its .aff file with their counts and line numbers, exiting with status 1 if any are found")
+            .action(ArgAction::Set))
+        .arg(Arg::new("personal-words")
+            .long("personal-words")
+            .value_name("PATH")
+            .value_parser(value_parser!(PathBuf))
+            .requires("spellcheck")
+            .help("Accepts the words of a personal word list, one per line, in addition to the dictionary")
+            .action(ArgAction::Set))
+        .arg(Arg::new("max-words")
+            .long("max-words")
+            .value_name("N")
+            .value_parser(value_parser!(usize))
+            .help("Reports inputs with more than N words, exiting with status 1, checking the files of directories one by one")
+            .action(ArgAction::Set))
+        .arg(Arg::new("min-words")
+            .long("min-words")
+            .value_name("N")
+            .value_parser(value_parser!(usize))
+            .help("Reports inputs with fewer than N words, exiting with status 1")
+            .action(ArgAction::Set))
+        .arg(Arg::new("max-lines")
+            .long("max-lines")
+            .value_name("N")
+            .value_parser(value_parser!(usize))
+            .help("Reports inputs with more than N non-empty lines, exiting with status 1")
+            .action(ArgAction::Set))
+        .arg(Arg::new("max-sentence-length")
+            .long("max-sentence-length")
+            .value_name("WORDS")
+            .value_parser(value_parser!(usize))
+            .help("Reports sentences longer than the given number of words with their line, exiting with status 1")
+            .action(ArgAction::Set))
+        .arg(Arg::new("forbid")
+            .long("forbid")
+            .value_name("WORD")
+            .value_delimiter(',')
+            .help("Reports each occurrence of the word or words with its line, exiting with status 1")
+            .action(ArgAction::Append))
+        .group(ArgGroup::new("limits")
+            .args(["max-words", "min-words", "max-lines", "max-sentence-length", "forbid"])
+            .multiple(true)
+            .conflicts_with_all(["top", "include-unique", "histogram", "entities", "chars", "graphemes", "lint-chars", "spellcheck", "zipf", "zipf-export", "compare", "tfidf", "approx", "hll", "counts-export"]))
+        .arg(Arg::new("zipf")
+            .long("zipf")
+            .help("Displays a Zipf's law rank-frequency fit with deviations of the top ranks")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("zipf-export")
+            .long("zipf-export")
+            .value_name("PATH")
+            .value_parser(value_parser!(PathBuf))
+            .help("Exports rank-frequency pairs as CSV to the provided path for plotting")
+            .action(ArgAction::Set))
+        .arg(Arg::new("compare")
+            .long("compare")
+            .alias("diff")
+            .value_name("FILENAME")
+            .value_parser(value_parser!(PathBuf))
+            .help("Compares word frequencies against a second file counted with identical options")
+            .action(ArgAction::Set))
+        .arg(Arg::new("sort-by")
+            .long("sort-by")
+            .value_name("delta|keyness")
+            .value_parser(["delta", "keyness"])
+            .default_value("delta")
+            .requires("compare")
+            .help("Sorts compared words by absolute count delta or log-likelihood keyness")
+            .action(ArgAction::Set))
+        .arg(Arg::new("tfidf")
+            .long("tfidf")
+            .help("Displays the terms most characteristic of each file relative to the rest using TF-IDF")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("tfidf-layout")
+            .long("tfidf-layout")
+            .value_name("per-file|combined")
+            .value_parser(["per-file", "combined"])
+            .requires("tfidf")
+            .help("Renders TF-IDF rankings as one table per file or a single combined table")
+            .action(ArgAction::Set))
+        .arg(Arg::new("smooth-idf")
+            .long("smooth-idf")
+            .requires("tfidf")
+            .help("Smooths inverse document frequencies so terms in every file keep a non-zero weight")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("sublinear-tf")
+            .long("sublinear-tf")
+            .requires("tfidf")
+            .help("Uses logarithmic term frequencies to dampen very frequent terms")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("approx")
+            .long("approx")
+            .conflicts_with_all(["histogram", "entities", "chars", "graphemes", "zipf", "zipf-export", "compare", "tfidf"])
+            .help("Approximates the top N words in bounded memory, reporting error bounds for each count and estimating the unique count with HyperLogLog if requested")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("approx-capacity")
+            .long("approx-capacity")
+            .value_name("N")
+            .value_parser(value_parser!(usize))
+            .requires("approx")
+            .help(format!("Number of words tracked in approximate mode, bounding memory use to about N times the length of a word rather than a number of bytes [default: {}]", approx::DEFAULT_CAPACITY))
+            .action(ArgAction::Set))
+        .arg(Arg::new("hll")
+            .long("hll")
+            .alias("estimate-unique")
+            .conflicts_with_all(["histogram", "entities", "chars", "graphemes", "zipf", "zipf-export", "compare", "tfidf"])
+            .help("Estimates the unique word count with HyperLogLog in constant memory instead of building the frequency map")
+            .action(ArgAction::SetTrue))
+        .arg(Arg::new("hll-precision")
+            .long("hll-precision")
+            .value_name("P")
+            .value_parser(value_parser!(u8).range(hll::MIN_PRECISION as i64..=hll::MAX_PRECISION as i64))
+            .requires("hll")
+            .help(format!("Precision of the HyperLogLog estimate using 2^P bytes of memory [default: {}]", hll::DEFAULT_PRECISION))
+            .action(ArgAction::Set))
         .get_matches();
 
-    let filename = matches.get_one::<PathBuf>("file").map(|p| p.to_path_buf()).unwrap();
+    let filenames = matches.get_many::<PathBuf>("file").map(|v| v.cloned().collect::<Vec<_>>()).unwrap_or_default();
+    let encoding = matches.get_one::<String>("encoding").and_then(|e| Encoding::from_label(e));
+    let binary_mode = match matches.get_one::<String>("binary-files").map(String::as_str) {
+        Some("skip") => BinaryMode::Skip,
+        Some("text") => BinaryMode::Text,
+        _ => BinaryMode::Warn,
+    };
+    let member_filter = MemberFilter {
+        include: matches.get_many::<String>("include").map(|v| v.cloned().collect()).unwrap_or_default(),
+        exclude: matches.get_many::<String>("exclude").map(|v| v.cloned().collect()).unwrap_or_default(),
+    };
+    let markdown = matches.get_flag("markdown").then(|| {
+        let included: Vec<&str> = matches.get_many::<String>("markdown-include").map(|v| v.map(String::as_str).collect()).unwrap_or_default();
+        MarkdownOptions {
+            is_include_code: included.contains(&"code"),
+            is_include_links: included.contains(&"links"),
+            is_include_front_matter: included.contains(&"front-matter"),
+            is_include_tables: included.contains(&"tables"),
+        }
+    });
+    let markup = if matches.get_flag("html") {
+        Some(MarkupKind::Html)
+    } else if matches.get_flag("xml") {
+        Some(MarkupKind::Xml)
+    } else {
+        None
+    };
+    let selector = matches.get_one::<Selector>("select").cloned();
+    let code_part = matches.get_one::<String>("code").is_some().then(|| match matches.get_one::<String>("code-part").map(String::as_str) {
+        Some("docs") => CodePart::DocComments,
+        Some("strings") => CodePart::Strings,
+        Some("identifiers") => CodePart::Identifiers,
+        _ => CodePart::Comments,
+    });
+    let code_language = matches.get_one::<String>("code").and_then(|l| Language::from_label(l));
+    let is_latex = matches.get_flag("latex");
+    let subtitle_max_wpm = matches.get_flag("subtitles").then(|| matches.get_one::<f64>("max-wpm").copied().unwrap_or(subtitle::DEFAULT_MAX_WPM));
+    let mail = matches.get_flag("mail").then(|| {
+        let included: Vec<&str> = matches.get_many::<String>("mail-include").map(|v| v.map(String::as_str).collect()).unwrap_or_default();
+        MailOptions { is_include_headers: included.contains(&"headers"), is_include_quotes: included.contains(&"quotes") }
+    });
+    let mail_group = matches.get_one::<String>("mail-group").and_then(|g| MailGroup::from_label(g));
+    let data_field = matches.get_one::<JsonPath>("json-path").cloned().map(DataField::JsonPath)
+        .or_else(|| matches.get_one::<String>("csv-column").cloned().map(DataField::CsvColumn));
+    let counts_export = matches.get_one::<PathBuf>("counts-export").cloned();
     let top_words = matches.get_one::<usize>("top").copied(); // Get the top N value, if provided
     let is_cased = matches.get_flag("cased");
     let ignore_words: Option<Vec<String>> = matches.get_many::<String>("ignore").map_or_else(|| None, |v| Some(v
@@ -92,7 +487,44 @@ pub fn parse_args() -> WCArgs {
     let is_include_all = matches.get_flag("include-all");
     let is_include_stopwords = matches.get_flag("include-stopwords");
     let is_include_unique = matches.get_flag("include-unique");
+    let is_bars = matches.get_flag("bars");
+    let zipf_export = matches.get_one::<PathBuf>("zipf-export").cloned();
+    let is_zipf = matches.get_flag("zipf") || zipf_export.is_some();
+    let compare_filename = matches.get_one::<PathBuf>("compare").cloned();
+    let compare_sort = if matches.get_one::<String>("sort-by").is_some_and(|s| s == "keyness") { CompareSort::Keyness } else { CompareSort::Delta };
+    let tfidf_layout = matches.get_flag("tfidf").then(|| match matches.get_one::<String>("tfidf-layout").map(String::as_str) {
+        Some("combined") => TfIdfLayout::Combined,
+        _ => TfIdfLayout::PerFile,
+    });
+    let tfidf_options = TfIdfOptions { is_smooth_idf: matches.get_flag("smooth-idf"), is_sublinear_tf: matches.get_flag("sublinear-tf") };
+    let approx_capacity = matches.get_flag("approx").then(|| matches.get_one::<usize>("approx-capacity").copied().unwrap_or(approx::DEFAULT_CAPACITY));
+    // Approximate mode never builds the full word map, so a requested unique count is estimated with HyperLogLog
+    let hll_precision = (matches.get_flag("hll") || (approx_capacity.is_some() && is_include_unique))
+        .then(|| matches.get_one::<u8>("hll-precision").copied().unwrap_or(hll::DEFAULT_PRECISION));
+    let histogram = matches.get_one::<String>("histogram").map(|h| if h == "lines" { HistogramKind::Lines } else { HistogramKind::Words });
+    let entity_kinds = matches.get_flag("entities").then(|| match matches.get_many::<String>("entity-kinds") {
+        Some(kinds) => kinds.filter_map(|k| EntityKind::from_label(k)).collect(),
+        None => EntityKind::ALL.to_vec(),
+    });
+    let char_unit = if matches.get_flag("graphemes") {
+        Some(CharUnit::Graphemes)
+    } else {
+        matches.get_flag("chars").then_some(CharUnit::Chars)
+    };
+    let lint_kinds = matches.get_flag("lint-chars").then(|| match matches.get_many::<String>("lint-kinds") {
+        Some(kinds) => kinds.filter_map(|k| LintKind::from_label(k)).collect(),
+        None => LintKind::ALL.to_vec(),
+    });
+    let spell_dictionary = matches.get_one::<PathBuf>("spellcheck").cloned();
+    let personal_words = matches.get_one::<PathBuf>("personal-words").cloned();
+    let limits = matches.contains_id("limits").then(|| Limits {
+        max_words: matches.get_one::<usize>("max-words").copied(),
+        min_words: matches.get_one::<usize>("min-words").copied(),
+        max_lines: matches.get_one::<usize>("max-lines").copied(),
+        max_sentence_words: matches.get_one::<usize>("max-sentence-length").copied(),
+        forbidden: matches.get_many::<String>("forbid").map(|v| v.map(|w| wclib::normalize_token(w.trim(), is_cased, is_include_all)).filter(|w| !w.is_empty()).collect()).unwrap_or_default(),
+    });
     
     // Return the primary struct
-    WCArgs { filename, top_words, ignore_words, is_cased, is_include_all, is_include_stopwords, is_include_unique }
+    WCArgs { filenames, encoding, binary_mode, member_filter, markdown, markup, selector, code_part, code_language, is_latex, subtitle_max_wpm, mail, mail_group, data_field, counts_export, top_words, ignore_words, is_cased, is_include_all, is_include_stopwords, is_include_unique, histogram, is_bars, entity_kinds, char_unit, lint_kinds, spell_dictionary, personal_words, limits, is_zipf, zipf_export, compare_filename, compare_sort, tfidf_layout, tfidf_options, approx_capacity, hll_precision }
 }
\ No newline at end of file
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use unicode_properties::{GeneralCategory, GeneralCategoryGroup, UnicodeGeneralCategory};
+use unicode_script::UnicodeScript;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{Table, TableStyle};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines the unit characters are tallied in.
+pub enum CharUnit {
+    Chars, // Individual Unicode scalar values
+    Graphemes // Extended grapheme clusters, so a letter with combining accents or an emoji sequence counts once
+}
+
+#[derive(Debug, Clone, Default)]
+/// Occurrences of each distinct character or grapheme cluster, merged across inputs. Line breaks are not counted.
+pub struct CharCounts {
+    counts: HashMap<String, usize>,
+}
+
+impl CharCounts {
+    /// Tallies the characters or grapheme clusters of the text.
+    pub fn add_text(&mut self, text: &str, unit: CharUnit) {
+        let mut add = |unit: &str| if !unit.chars().all(|c| c == '\n' || c == '\r') {
+            match self.counts.get_mut(unit) {
+                Some(count) => *count += 1,
+                None => { self.counts.insert(unit.to_string(), 1); },
+            }
+        };
+        match unit {
+            CharUnit::Chars => text.char_indices().for_each(|(i, c)| add(&text[i..i + c.len_utf8()])),
+            CharUnit::Graphemes => text.graphemes(true).for_each(add),
+        }
+    }
+    /// Returns the total number of characters or grapheme clusters tallied.
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+    /// Returns the number of distinct characters or grapheme clusters tallied.
+    pub fn distinct(&self) -> usize {
+        self.counts.len()
+    }
+    /// Returns the distinct units sorted by descending count, then by code point.
+    fn ranked(&self) -> Vec<(&str, usize)> {
+        let mut ranked: Vec<(&str, usize)> = self.counts.iter().map(|(u, c)| (u.as_str(), *c)).collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        ranked
+    }
+    /// Sums the counts by the key of each unit, sorted by descending count.
+    fn grouped<K: Ord + std::hash::Hash>(&self, key: impl Fn(&str) -> K) -> Vec<(K, usize)> {
+        let mut groups: HashMap<K, usize> = HashMap::new();
+        for (unit, count) in self.counts.iter() {
+            *groups.entry(key(unit)).or_insert(0) += count;
+        }
+        let mut grouped: Vec<(K, usize)> = groups.into_iter().collect();
+        grouped.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        grouped
+    }
+}
+
+/// Returns the first character of a unit, which determines the category and script of a grapheme cluster.
+fn base_char(unit: &str) -> char {
+    unit.chars().next().unwrap_or_default()
+}
+
+/// Returns the two letter abbreviation and the description of a general category, like `Zs` and `space separator`.
+fn category_label(category: GeneralCategory) -> (&'static str, &'static str) {
+    match category {
+        GeneralCategory::UppercaseLetter => ("Lu", "uppercase letter"),
+        GeneralCategory::LowercaseLetter => ("Ll", "lowercase letter"),
+        GeneralCategory::TitlecaseLetter => ("Lt", "titlecase letter"),
+        GeneralCategory::ModifierLetter => ("Lm", "modifier letter"),
+        GeneralCategory::OtherLetter => ("Lo", "other letter"),
+        GeneralCategory::NonspacingMark => ("Mn", "nonspacing mark"),
+        GeneralCategory::SpacingMark => ("Mc", "spacing mark"),
+        GeneralCategory::EnclosingMark => ("Me", "enclosing mark"),
+        GeneralCategory::DecimalNumber => ("Nd", "decimal number"),
+        GeneralCategory::LetterNumber => ("Nl", "letter number"),
+        GeneralCategory::OtherNumber => ("No", "other number"),
+        GeneralCategory::ConnectorPunctuation => ("Pc", "connector punctuation"),
+        GeneralCategory::DashPunctuation => ("Pd", "dash punctuation"),
+        GeneralCategory::OpenPunctuation => ("Ps", "open punctuation"),
+        GeneralCategory::ClosePunctuation => ("Pe", "close punctuation"),
+        GeneralCategory::InitialPunctuation => ("Pi", "initial quote"),
+        GeneralCategory::FinalPunctuation => ("Pf", "final quote"),
+        GeneralCategory::OtherPunctuation => ("Po", "other punctuation"),
+        GeneralCategory::MathSymbol => ("Sm", "math symbol"),
+        GeneralCategory::CurrencySymbol => ("Sc", "currency symbol"),
+        GeneralCategory::ModifierSymbol => ("Sk", "modifier symbol"),
+        GeneralCategory::OtherSymbol => ("So", "other symbol"),
+        GeneralCategory::SpaceSeparator => ("Zs", "space separator"),
+        GeneralCategory::LineSeparator => ("Zl", "line separator"),
+        GeneralCategory::ParagraphSeparator => ("Zp", "paragraph separator"),
+        GeneralCategory::Control => ("Cc", "control"),
+        GeneralCategory::Format => ("Cf", "format"),
+        GeneralCategory::Surrogate => ("Cs", "surrogate"),
+        GeneralCategory::PrivateUse => ("Co", "private use"),
+        GeneralCategory::Unassigned => ("Cn", "unassigned"),
+    }
+}
+
+/// Formats the code points of a unit like `U+00E9` or `U+0065 U+0301`.
+pub fn fmt_code_points(unit: &str) -> String {
+    unit.chars().map(|c| format!("U+{:04X}", c as u32)).collect::<Vec<_>>().join(" ")
+}
+
+/// Formats a unit for display, escaping spaces, controls and format characters that would otherwise be invisible in the table,
+/// and showing a lone combining mark on a dotted circle.
+fn fmt_unit(unit: &str) -> String {
+    let c = base_char(unit);
+    match c.general_category_group() {
+        GeneralCategoryGroup::Separator | GeneralCategoryGroup::Other => c.escape_unicode().to_string(),
+        GeneralCategoryGroup::Mark => format!("◌{unit}"),
+        _ => unit.to_string(),
+    }
+}
+
+/// Create a table of the `top_n` most frequent characters or grapheme clusters with their code points, general category and script.
+pub fn generate_char_table(counts: &CharCounts, top_n: usize, style: TableStyle) -> Table<String, String> {
+    let headers = vec!["#", "char", "code", "category", "script", "ct"].into_iter().map(String::from).collect();
+    let data = counts.ranked().into_iter().take(top_n).enumerate()
+        .map(|(i, (unit, count))| {
+            let c = base_char(unit);
+            vec![(i + 1).to_string(), fmt_unit(unit), fmt_code_points(unit), category_label(c.general_category()).0.to_string(), c.script().full_name().to_string(), count.to_string()]
+        })
+        .collect();
+    Table::new_with_style(headers, data, style, "><<<<>")
+}
+
+/// Create a table of the counts per general category, like `Ll lowercase letter`, with the percentage of all characters.
+pub fn generate_category_table(counts: &CharCounts, style: TableStyle) -> Table<String, String> {
+    let headers = vec!["category", "name", "ct"].into_iter().map(String::from).collect();
+    let data = counts.grouped(|unit| category_label(base_char(unit).general_category())).into_iter()
+        .map(|((abbreviation, name), count)| vec![abbreviation.to_string(), name.to_string(), count.to_string()])
+        .collect();
+    let mut table = Table::new_with_style(headers, data, style, "<<>");
+    table.add_percent_column(2, counts.total());
+    table
+}
+
+/// Create a table of the counts per script with the percentage of all characters, where `Common` covers punctuation, digits and
+/// spaces shared across scripts and `Inherited` covers combining marks.
+pub fn generate_script_table(counts: &CharCounts, style: TableStyle) -> Table<String, String> {
+    let headers = vec!["script", "ct"].into_iter().map(String::from).collect();
+    let data = counts.grouped(|unit| base_char(unit).script().full_name()).into_iter()
+        .map(|(script, count)| vec![script.to_string(), count.to_string()])
+        .collect();
+    let mut table = Table::new_with_style(headers, data, style, "<>");
+    table.add_percent_column(1, counts.total());
+    table
+}
+
+/// Create a table of every punctuation character or grapheme cluster by descending count, which tells straight and smart quotes,
+/// hyphens and dashes apart. Returns `None` if there was no punctuation.
+pub fn generate_punctuation_table(counts: &CharCounts, style: TableStyle) -> Option<Table<String, String>> {
+    let punctuation: Vec<(&str, usize)> = counts.ranked().into_iter()
+        .filter(|(unit, _)| base_char(unit).general_category_group() == GeneralCategoryGroup::Punctuation)
+        .collect();
+    if punctuation.is_empty() {
+        return None
+    }
+    let headers = vec!["punct", "code", "category", "ct"].into_iter().map(String::from).collect();
+    let data = punctuation.into_iter()
+        .map(|(unit, count)| {
+            let (abbreviation, name) = category_label(base_char(unit).general_category());
+            vec![unit.to_string(), fmt_code_points(unit), format!("{abbreviation} {name}"), count.to_string()]
+        })
+        .collect();
+    Some(Table::new_with_style(headers, data, style, "<<<>"))
+}
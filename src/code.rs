@@ -0,0 +1,438 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines the programming languages whose source can be lexed into comments, string literals and identifiers.
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript, // Includes TypeScript
+    C, // Includes C++
+    Go,
+    Java,
+    Shell
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines which part of the source code is counted.
+pub enum CodePart {
+    Comments, // All comments, including doc comments, without their markers
+    DocComments, // Only documentation comments and Python docstrings
+    Strings, // Only the contents of string literals
+    Identifiers // Only identifiers, split into words on camelCase and snake_case boundaries
+}
+
+/// Lexical rules of a language, shared by the single lexer that handles every supported language.
+struct Syntax {
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    is_nested_block_comments: bool,
+    doc_comments: &'static [&'static str], // Comment openers marking documentation, longest first
+    string_delimiters: &'static [&'static str], // Longest first so triple quotes are matched before single quotes
+    raw_delimiters: &'static [&'static str], // Delimiters whose strings have no escape sequences
+    string_prefixes: &'static [&'static str], // Identifiers that prefix a string literal rather than being identifiers
+    is_quote_char_literal: bool, // Single quotes delimit character literals (or lifetimes in Rust) rather than strings
+    keywords: &'static [&'static str],
+}
+
+const RUST_SYNTAX: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    is_nested_block_comments: true,
+    doc_comments: &["///", "//!", "/**", "/*!"],
+    string_delimiters: &["\""],
+    raw_delimiters: &[],
+    string_prefixes: &["b", "c", "r", "br", "cr"],
+    is_quote_char_literal: true,
+    keywords: &["as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while"],
+};
+
+const PYTHON_SYNTAX: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    is_nested_block_comments: false,
+    doc_comments: &[],
+    string_delimiters: &["\"\"\"", "'''", "\"", "'"],
+    raw_delimiters: &[],
+    string_prefixes: &["r", "b", "f", "u", "rb", "br", "fr", "rf", "R", "B", "F", "U", "Rb", "bR", "RB", "BR", "Fr", "fR", "FR", "RF", "rB", "Br", "rF", "Rf"],
+    is_quote_char_literal: false,
+    keywords: &["False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "self", "try", "while", "with", "yield"],
+};
+
+const JAVASCRIPT_SYNTAX: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    is_nested_block_comments: false,
+    doc_comments: &["/**"],
+    string_delimiters: &["\"", "'", "`"],
+    raw_delimiters: &[],
+    string_prefixes: &[],
+    is_quote_char_literal: false,
+    keywords: &["async", "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete", "do", "else", "enum", "export", "extends", "false", "finally", "for", "function", "if", "implements", "import", "in", "instanceof", "interface", "let", "new", "null", "of", "private", "protected", "public", "readonly", "return", "static", "super", "switch", "this", "throw", "true", "try", "type", "typeof", "undefined", "var", "void", "while", "with", "yield"],
+};
+
+const C_SYNTAX: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    is_nested_block_comments: false,
+    doc_comments: &["///", "//!", "/**", "/*!"],
+    string_delimiters: &["\""],
+    raw_delimiters: &[],
+    string_prefixes: &["L", "u", "U", "u8", "R"],
+    is_quote_char_literal: true,
+    keywords: &["auto", "bool", "break", "case", "char", "class", "const", "continue", "default", "define", "delete", "do", "double", "else", "endif", "enum", "extern", "false", "float", "for", "goto", "if", "ifdef", "ifndef", "include", "inline", "int", "long", "namespace", "new", "nullptr", "private", "protected", "public", "register", "return", "short", "signed", "sizeof", "static", "struct", "switch", "template", "this", "true", "typedef", "typename", "union", "unsigned", "using", "virtual", "void", "volatile", "while"],
+};
+
+const GO_SYNTAX: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    is_nested_block_comments: false,
+    doc_comments: &[], // Go doc comments are ordinary comments directly above a declaration, see `is_go_doc_comment`
+    string_delimiters: &["\"", "`"],
+    raw_delimiters: &["`"],
+    string_prefixes: &[],
+    is_quote_char_literal: true,
+    keywords: &["break", "case", "chan", "const", "continue", "default", "defer", "else", "fallthrough", "false", "for", "func", "go", "goto", "if", "import", "interface", "map", "nil", "package", "range", "return", "select", "struct", "switch", "true", "type", "var"],
+};
+
+const JAVA_SYNTAX: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    is_nested_block_comments: false,
+    doc_comments: &["/**"],
+    string_delimiters: &["\"\"\"", "\""],
+    raw_delimiters: &[],
+    string_prefixes: &[],
+    is_quote_char_literal: true,
+    keywords: &["abstract", "boolean", "break", "byte", "case", "catch", "char", "class", "const", "continue", "default", "do", "double", "else", "enum", "extends", "false", "final", "finally", "float", "for", "goto", "if", "implements", "import", "instanceof", "int", "interface", "long", "native", "new", "null", "package", "private", "protected", "public", "return", "short", "static", "super", "switch", "synchronized", "this", "throw", "throws", "transient", "true", "try", "var", "void", "volatile", "while"],
+};
+
+const SHELL_SYNTAX: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comment: None,
+    is_nested_block_comments: false,
+    doc_comments: &[],
+    string_delimiters: &["\"", "'"],
+    raw_delimiters: &["'"],
+    string_prefixes: &[],
+    is_quote_char_literal: false,
+    keywords: &["case", "do", "done", "elif", "else", "esac", "exit", "export", "fi", "for", "function", "if", "in", "local", "return", "select", "then", "time", "until", "while"],
+};
+
+impl Language {
+    /// Parses a language name, accepting common aliases like `ts`, `cpp` and `bash`.
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Some(Self::Rust),
+            "python" | "py" => Some(Self::Python),
+            "javascript" | "js" | "typescript" | "ts" => Some(Self::JavaScript),
+            "c" | "c++" | "cpp" => Some(Self::C),
+            "go" => Some(Self::Go),
+            "java" => Some(Self::Java),
+            "shell" | "sh" | "bash" | "zsh" => Some(Self::Shell),
+            _ => None,
+        }
+    }
+    /// Identifies the language from the file extension.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" | "pyw" => Some(Self::Python),
+            "js" | "mjs" | "cjs" | "jsx" | "ts" | "mts" | "cts" | "tsx" => Some(Self::JavaScript),
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Some(Self::C),
+            "go" => Some(Self::Go),
+            "java" => Some(Self::Java),
+            "sh" | "bash" | "zsh" | "ksh" => Some(Self::Shell),
+            _ => None,
+        }
+    }
+    /// Identifies the language of a script from the interpreter named by its shebang line.
+    pub fn from_shebang(text: &str) -> Option<Self> {
+        let interpreter = text.lines().next()?.strip_prefix("#!")?;
+        let interpreter = interpreter.split_whitespace().find(|part| !part.ends_with("/env") && !part.starts_with('-'))?;
+        match interpreter.rsplit('/').next()?.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+            "python" => Some(Self::Python),
+            "node" | "deno" => Some(Self::JavaScript),
+            "sh" | "bash" | "zsh" | "ksh" | "dash" => Some(Self::Shell),
+            _ => None,
+        }
+    }
+    /// Returns the name of the language.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Rust => "rust",
+            Self::Python => "python",
+            Self::JavaScript => "javascript",
+            Self::C => "c",
+            Self::Go => "go",
+            Self::Java => "java",
+            Self::Shell => "shell",
+        }
+    }
+    fn syntax(&self) -> &'static Syntax {
+        match self {
+            Self::Rust => &RUST_SYNTAX,
+            Self::Python => &PYTHON_SYNTAX,
+            Self::JavaScript => &JAVASCRIPT_SYNTAX,
+            Self::C => &C_SYNTAX,
+            Self::Go => &GO_SYNTAX,
+            Self::Java => &JAVA_SYNTAX,
+            Self::Shell => &SHELL_SYNTAX,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines the kinds of tokens produced by the lexer, everything else in the source is skipped.
+pub enum TokenKind {
+    Comment,
+    DocComment,
+    String,
+    Identifier
+}
+
+#[derive(Debug, Clone)]
+/// A comment, string literal or identifier with comment markers and string delimiters removed.
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: Cow<'a, str>,
+    pub line: usize, // 0-based line the token starts on
+}
+
+/// Returns true if the Go line comment starting at `start` is a doc comment, i.e. it is unindented and its comment block
+/// is directly followed by a top-level declaration.
+fn is_go_doc_comment(text: &str, start: usize) -> bool {
+    if start > 0 && text.as_bytes()[start - 1] != b'\n' {
+        return false
+    }
+    text[start..].lines().find(|line| !line.starts_with("//"))
+        .is_some_and(|line| ["func ", "type ", "var ", "const ", "package "].iter().any(|k| line.starts_with(k)))
+}
+
+/// Removes the leading `*` decoration and indentation from each line of a block comment body.
+fn clean_block_comment(body: &str) -> String {
+    body.lines().map(|line| line.trim_start().trim_start_matches('*').trim()).collect::<Vec<_>>().join("\n")
+}
+
+/// Replaces common escape sequences in a string literal with the characters they stand for, turning escaped whitespace into a space.
+fn unescape(body: &str) -> Cow<'_, str> {
+    if !body.contains('\\') {
+        return Cow::Borrowed(body)
+    }
+    let mut unescaped = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 't' | 'r') => unescaped.push(' '),
+            Some(escaped) => unescaped.push(escaped),
+            None => {},
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+/// Returns the byte length of the identifier at the start of `text`, or 0 if it does not start with one.
+fn identifier_len(text: &str) -> usize {
+    if !text.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        return 0
+    }
+    text.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(text.len())
+}
+
+/// Splits the source into comments, string literals and identifiers. Keywords, numbers, operators and character literals are skipped.
+/// The lexer is deliberately approximate: it does not understand JavaScript regular expression literals, template interpolation or shell heredocs.
+pub fn lex(text: &str, language: Language) -> Vec<Token<'_>> {
+    let syntax = language.syntax();
+    let mut tokens = Vec::new();
+    let mut line = 0_usize;
+    let mut last_code_char: Option<char> = None; // Last non-whitespace character outside comments, used to detect Python docstrings
+    let mut pos = 0_usize;
+    while pos < text.len() {
+        let rest = &text[pos..];
+        let c = rest.chars().next().unwrap_or_default();
+
+        // Shell comments only start at the beginning of a word, so `${#var}` is not a comment
+        let is_comment_allowed = language != Language::Shell
+            || text[..pos].chars().next_back().is_none_or(|p| p.is_whitespace() || matches!(p, ';' | '|' | '&' | '(' | ')'));
+        // A shebang line names the interpreter rather than being a comment, unlike a Rust inner attribute like `#![deny(missing_docs)]`
+        if pos == 0 && rest.starts_with("#!") && !rest.starts_with("#![") {
+            pos = rest.find('\n').unwrap_or(rest.len());
+            continue;
+        }
+        if let Some(marker) = syntax.line_comments.iter().find(|m| is_comment_allowed && rest.starts_with(**m)) {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            let doc_marker = syntax.doc_comments.iter().find(|d| rest.starts_with(**d) && !rest[d.len()..].starts_with(&marker[..1]));
+            let is_doc = doc_marker.is_some() || (language == Language::Go && is_go_doc_comment(text, pos));
+            let body = &rest[doc_marker.map_or(marker.len(), |d| d.len())..end];
+            tokens.push(Token { kind: if is_doc { TokenKind::DocComment } else { TokenKind::Comment }, text: Cow::Borrowed(body.trim()), line });
+            pos += end;
+            continue;
+        }
+        if let Some((open, close)) = syntax.block_comment.filter(|(open, _)| rest.starts_with(open)) {
+            let mut depth = 0_usize;
+            let mut end = rest.len();
+            let mut i = 0;
+            while i < rest.len() {
+                if rest[i..].starts_with(open) && (depth == 0 || syntax.is_nested_block_comments) {
+                    depth += 1;
+                    i += open.len();
+                } else if rest[i..].starts_with(close) {
+                    depth -= 1;
+                    i += close.len();
+                    if depth == 0 {
+                        end = i;
+                        break;
+                    }
+                } else {
+                    i += rest[i..].chars().next().map_or(1, char::len_utf8);
+                }
+            }
+            let comment = &rest[..end];
+            // An empty `/**/` is not a doc comment
+            let doc_marker = syntax.doc_comments.iter().find(|d| comment.starts_with(**d) && comment.len() > d.len() + 1 && !comment[d.len()..].starts_with('*'));
+            let body = comment[doc_marker.map_or(open.len(), |d| d.len())..].strip_suffix(close).unwrap_or_default();
+            tokens.push(Token { kind: if doc_marker.is_some() { TokenKind::DocComment } else { TokenKind::Comment }, text: Cow::Owned(clean_block_comment(body)), line });
+            line += comment.matches('\n').count();
+            pos += end;
+            continue;
+        }
+
+        let prefix_len = identifier_len(rest);
+        let prefix = &rest[..prefix_len];
+        let after_prefix = &rest[prefix_len..];
+        // Rust raw strings like `r#"..."#` close with the same number of hashes they open with
+        if language == Language::Rust && matches!(prefix, "r" | "br" | "cr") && after_prefix.trim_start_matches('#').starts_with('"') {
+            let hashes = after_prefix.len() - after_prefix.trim_start_matches('#').len();
+            let terminator = format!("\"{}", "#".repeat(hashes));
+            let body_start = prefix_len + hashes + 1;
+            let body_len = rest[body_start..].find(&terminator).unwrap_or(rest.len() - body_start);
+            let body = &rest[body_start..body_start + body_len];
+            tokens.push(Token { kind: TokenKind::String, text: Cow::Borrowed(body), line });
+            line += body.matches('\n').count();
+            last_code_char = Some('"');
+            pos += (body_start + body_len + terminator.len()).min(rest.len());
+            continue;
+        }
+        let is_string_prefix = prefix_len > 0 && syntax.string_prefixes.contains(&prefix) && syntax.string_delimiters.iter().any(|d| after_prefix.starts_with(d));
+        let string_rest = if is_string_prefix { after_prefix } else { rest };
+        if let Some(delimiter) = syntax.string_delimiters.iter().find(|d| string_rest.starts_with(**d) && (!syntax.is_quote_char_literal || **d != "'")) {
+            let is_raw = syntax.raw_delimiters.contains(delimiter) || (is_string_prefix && language == Language::Python && prefix.contains(['r', 'R']));
+            let start = rest.len() - string_rest.len() + delimiter.len();
+            let mut i = start;
+            while i < rest.len() && !rest[i..].starts_with(delimiter) {
+                i += if rest[i..].starts_with('\\') && !is_raw { 1 + rest[i + 1..].chars().next().map_or(0, char::len_utf8) } else { rest[i..].chars().next().map_or(1, char::len_utf8) };
+            }
+            let body = &rest[start..i.min(rest.len())];
+            // A triple-quoted string that starts a module or follows a `def` or `class` line is a docstring
+            let is_docstring = language == Language::Python && delimiter.len() == 3 && last_code_char.is_none_or(|p| p == ':');
+            tokens.push(Token { kind: if is_docstring { TokenKind::DocComment } else { TokenKind::String }, text: if is_raw { Cow::Borrowed(body) } else { unescape(body) }, line });
+            line += body.matches('\n').count();
+            last_code_char = Some('"');
+            pos += (i + delimiter.len()).min(rest.len());
+            continue;
+        }
+        if prefix_len > 0 {
+            if !syntax.keywords.contains(&prefix) {
+                tokens.push(Token { kind: TokenKind::Identifier, text: Cow::Borrowed(prefix), line });
+            }
+            last_code_char = prefix.chars().next_back();
+            pos += prefix_len;
+            continue;
+        }
+        if c == '\'' && syntax.is_quote_char_literal {
+            // Character literals are skipped, while a quote not closed within one character is a Rust lifetime or label
+            let literal_len = if rest[1..].starts_with('\\') {
+                rest.get(3..).and_then(|r| r.find('\'')).map(|e| e + 4)
+            } else {
+                rest[1..].chars().next().filter(|ch| rest[1 + ch.len_utf8()..].starts_with('\'')).map(|ch| ch.len_utf8() + 2)
+            };
+            pos += literal_len.unwrap_or(1 + identifier_len(&rest[1..]));
+            last_code_char = Some('\'');
+            continue;
+        }
+        if c.is_ascii_digit() {
+            // Skip numbers along with suffixes like `10u32` or `0xFF` so they are not mistaken for identifiers
+            pos += rest.find(|ch: char| !(ch.is_alphanumeric() || ch == '_' || ch == '.')).unwrap_or(rest.len());
+            last_code_char = Some('0');
+            continue;
+        }
+        if c == '\n' {
+            line += 1;
+        } else if !c.is_whitespace() {
+            last_code_char = Some(c);
+        }
+        pos += c.len_utf8();
+    }
+    tokens
+}
+
+/// Splits an identifier into words on underscores, hyphens and camelCase boundaries, keeping acronyms together so `parseHTTPResponse_v2` gives `parse`, `HTTP`, `Response` and `v2`.
+pub fn split_identifier(identifier: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    for part in identifier.split(['_', '-']).filter(|p| !p.is_empty()) {
+        let chars: Vec<(usize, char)> = part.char_indices().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (prev, cur) = (chars[i - 1].1, chars[i].1);
+            let next = chars.get(i + 1).map(|(_, c)| *c);
+            // Split before an uppercase letter following a lowercase letter or digit, or before the last uppercase letter of an acronym followed by lowercase
+            let is_boundary = cur.is_uppercase() && (prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next.is_some_and(char::is_lowercase)));
+            if is_boundary {
+                words.push(&part[start..chars[i].0]);
+                start = chars[i].0;
+            }
+        }
+        words.push(&part[start..]);
+    }
+    words
+}
+
+/// Extracts the requested part of the source as text, with each token on the line it starts on in the source.
+pub fn extract(text: &str, language: Language, part: CodePart) -> String {
+    let mut extracted = String::with_capacity(text.len() / 4);
+    let mut current_line = 0_usize;
+    for token in lex(text, language) {
+        let is_wanted = match part {
+            CodePart::Comments => matches!(token.kind, TokenKind::Comment | TokenKind::DocComment),
+            CodePart::DocComments => token.kind == TokenKind::DocComment,
+            CodePart::Strings => token.kind == TokenKind::String,
+            CodePart::Identifiers => token.kind == TokenKind::Identifier,
+        };
+        if !is_wanted {
+            continue;
+        }
+        if token.line != current_line {
+            extracted.push('\n');
+            current_line = token.line;
+        } else if !extracted.is_empty() {
+            extracted.push(' ');
+        }
+        match token.kind {
+            TokenKind::Identifier => extracted.push_str(&split_identifier(&token.text).join(" ")),
+            _ => extracted.push_str(&token.text),
+        }
+    }
+    extracted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shebang_is_not_a_comment() {
+        let text = "#!/usr/bin/env python3\n# real comment\nx = 1\n";
+        assert_eq!(extract(text, Language::Python, CodePart::Comments).split_whitespace().collect::<Vec<_>>(), vec!["real", "comment"]);
+        assert_eq!(extract("#!/bin/sh\necho hi # greet\n", Language::Shell, CodePart::Comments).trim(), "greet");
+    }
+
+    #[test]
+    fn rust_inner_attribute_is_not_a_shebang() {
+        let identifiers = extract("#![deny(missing_docs)]\nfn main() {}\n", Language::Rust, CodePart::Identifiers);
+        assert_eq!(identifiers.split_whitespace().collect::<Vec<_>>(), vec!["deny", "missing", "docs", "main"]);
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashSet;
+
+use crate::{Table, TableStyle, WordMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines the ordering of rows when comparing word frequencies between two inputs.
+pub enum CompareSort {
+    Delta, // Largest absolute change in count first
+    Keyness // Largest log-likelihood (G²) score first
+}
+
+#[derive(Debug, Clone)]
+/// Holds the counts of a single word in both inputs along with the derived change and keyness statistics.
+pub struct WordDelta<'a> {
+    pub word: &'a str,
+    pub count_a: usize,
+    pub count_b: usize,
+    pub keyness: f64, // Log-likelihood (G²) score, larger values are more statistically distinctive
+}
+
+impl WordDelta<'_> {
+    /// Returns the signed change in count from input A to input B.
+    pub fn delta(&self) -> i64 {
+        self.count_b as i64 - self.count_a as i64
+    }
+    /// Returns the relative change in count from input A to input B, or `None` if the word does not occur in A.
+    pub fn relative_change(&self) -> Option<f64> {
+        if self.count_a == 0 { None } else { Some(self.delta() as f64 / self.count_a as f64) }
+    }
+}
+
+/// Returns the log-likelihood (G²) keyness score for a word occurring `a` times in a corpus of `total_a` words and `b` times in a corpus of `total_b` words.
+pub fn log_likelihood(a: usize, b: usize, total_a: usize, total_b: usize) -> f64 {
+    let (a, b, total_a, total_b) = (a as f64, b as f64, total_a as f64, total_b as f64);
+    if total_a + total_b == 0.0 {
+        return 0.0
+    }
+    let expected_a = total_a * (a + b) / (total_a + total_b);
+    let expected_b = total_b * (a + b) / (total_a + total_b);
+    // Terms with an observed count of zero contribute nothing to the sum
+    let term = |observed: f64, expected: f64| if observed > 0.0 && expected > 0.0 { observed * (observed / expected).ln() } else { 0.0 };
+    2.0 * (term(a, expected_a) + term(b, expected_b))
+}
+
+/// Compares two word frequency maps, keeping only the words passing `is_counted`, sorted by the requested ordering with ties broken alphabetically.
+pub fn compare_frequencies<'a>(word_map_a: &'a WordMap, word_map_b: &'a WordMap, is_counted: impl Fn(&str) -> bool, sort: CompareSort) -> Vec<WordDelta<'a>> {
+    let total_a: usize = word_map_a.values().sum();
+    let total_b: usize = word_map_b.values().sum();
+    let words: HashSet<&str> = word_map_a.keys().chain(word_map_b.keys()).map(|w| w.as_ref()).collect();
+    let mut deltas: Vec<WordDelta> = words.into_iter()
+        .filter(|word| is_counted(word))
+        .map(|word| {
+            let count_a = word_map_a.get(word).copied().unwrap_or(0);
+            let count_b = word_map_b.get(word).copied().unwrap_or(0);
+            WordDelta { word, count_a, count_b, keyness: log_likelihood(count_a, count_b, total_a, total_b) }
+        })
+        .collect();
+    match sort {
+        CompareSort::Delta => deltas.sort_by(|x, y| y.delta().abs().cmp(&x.delta().abs()).then_with(|| x.word.cmp(y.word))),
+        CompareSort::Keyness => deltas.sort_by(|x, y| y.keyness.total_cmp(&x.keyness).then_with(|| x.word.cmp(y.word))),
+    }
+    deltas
+}
+
+/// Create a table of the first `top_n` compared words with their counts in each input, change and keyness.
+pub fn generate_compare_table(deltas: &[WordDelta], top_n: usize, style: TableStyle) -> Table<String, String> {
+    let headers = vec!["#", "word", "A", "B", "delta", "change", "G²"].into_iter().map(String::from).collect();
+    let data = deltas.iter().take(top_n).enumerate()
+        .map(|(i, d)| {
+            let fmt_change = d.relative_change().map_or_else(|| "new".to_string(), |c| format!("{:+.1}%", c * 100.0));
+            vec![(i + 1).to_string(), d.word.to_string(), d.count_a.to_string(), d.count_b.to_string(), format!("{:+}", d.delta()), fmt_change, format!("{:.2}", d.keyness)]
+        })
+        .collect();
+    Table::new_with_style(headers, data, style, "><>>>>>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::borrow::Cow;
+
+    #[test]
+    fn log_likelihood_matches_known_scores() {
+        // Expected counts of 5 in each corpus give 2 * 10 * ln(10 / 5)
+        assert!((log_likelihood(10, 0, 1000, 1000) - 20.0 * 2f64.ln()).abs() < 1e-9);
+        // Expected counts of 75 give 2 * (100 * ln(100 / 75) + 50 * ln(50 / 75))
+        assert!((log_likelihood(100, 50, 100_000, 100_000) - 16.9899).abs() < 1e-3);
+        assert!((log_likelihood(3, 17, 500, 2000) - log_likelihood(17, 3, 2000, 500)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn log_likelihood_is_zero_without_difference() {
+        assert_eq!(log_likelihood(10, 20, 1000, 2000), 0.0);
+        assert_eq!(log_likelihood(0, 0, 1000, 1000), 0.0);
+        assert_eq!(log_likelihood(0, 0, 0, 0), 0.0);
+    }
+
+    #[test]
+    fn frequencies_sort_by_delta_then_word() {
+        let word_map_a: WordMap = [("the", 10), ("cat", 4), ("dog", 1)].into_iter().map(|(w, c)| (Cow::Borrowed(w), c)).collect();
+        let word_map_b: WordMap = [("the", 10), ("dog", 5), ("bird", 4)].into_iter().map(|(w, c)| (Cow::Borrowed(w), c)).collect();
+        let deltas = compare_frequencies(&word_map_a, &word_map_b, |word| word != "the", CompareSort::Delta);
+        let rows: Vec<_> = deltas.iter().map(|d| (d.word, d.delta(), d.relative_change())).collect();
+        assert_eq!(rows, [("bird", 4, None), ("cat", -4, Some(-1.0)), ("dog", 4, Some(4.0))]);
+    }
+}
@@ -0,0 +1,134 @@
+use std::io::{self, Read};
+use std::path::Path;
+
+use ruzstd::decoding::errors::{FrameDecoderError, ReadFrameHeaderError};
+use ruzstd::decoding::{FrameDecoder, StreamingDecoder};
+
+/// Magic of a bzip2 compressed block, the BCD digits of pi.
+const BZIP2_BLOCK_MAGIC: &[u8] = b"1AY&SY";
+
+/// Magic of the end of a bzip2 stream, the BCD digits of the square root of pi.
+const BZIP2_END_MAGIC: &[u8] = &[0x17, 0x72, 0x45, 0x38, 0x50, 0x90];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines the compression formats that are transparently decompressed before counting.
+pub enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd
+}
+
+impl Compression {
+    /// Identifies the compression format from the leading magic bytes. Bzip2 is only recognized with a block size digit followed by
+    /// the magic of a compressed block or of the end of an empty stream, since `BZh` alone is common at the start of plain text.
+    pub fn from_magic(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0x1F, 0x8B]) {
+            Some(Self::Gzip)
+        } else if bytes.starts_with(b"BZh") && bytes.get(3).is_some_and(|b| (b'1'..=b'9').contains(b))
+            && bytes.get(4..10).is_some_and(|magic| magic == BZIP2_BLOCK_MAGIC || magic == BZIP2_END_MAGIC) {
+            Some(Self::Bzip2)
+        } else if bytes.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Self::Xz)
+        } else if bytes.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+    /// Identifies the compression format from the file extension.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "gz" | "gzip" | "tgz" => Some(Self::Gzip),
+            "bz2" | "bzip2" | "tbz2" => Some(Self::Bzip2),
+            "xz" | "txz" => Some(Self::Xz),
+            "zst" | "zstd" | "tzst" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+    /// Returns the name of the compression format.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Bzip2 => "bzip2",
+            Self::Xz => "xz",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
+/// Detects the compression of a non-empty file from its magic bytes, falling back to its extension.
+pub fn detect(path: &Path, bytes: &[u8]) -> Option<Compression> {
+    if bytes.is_empty() {
+        return None
+    }
+    Compression::from_magic(bytes).or_else(|| Compression::from_extension(path))
+}
+
+/// Reads the content of consecutive zstd frames, skipping the skippable frames that carry metadata rather than content.
+struct ZstdFrames<'a> {
+    input: &'a [u8], // Compressed input after the current frame
+    decoder: Option<StreamingDecoder<&'a [u8], FrameDecoder>>,
+    frame_decoder: Option<FrameDecoder>, // Decoder of the last frame, reused for the next so its buffers are not reallocated
+}
+
+impl Read for ZstdFrames<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(decoder) = self.decoder.as_mut() {
+                let n = decoder.read(buf)?;
+                if n > 0 || buf.is_empty() {
+                    return Ok(n)
+                }
+                // The frame is exhausted, continue with the input after it
+                if let Some((input, frame_decoder)) = self.decoder.take().map(StreamingDecoder::into_parts) {
+                    self.input = input;
+                    self.frame_decoder = Some(frame_decoder);
+                }
+            }
+            if self.input.is_empty() {
+                return Ok(0)
+            }
+            let frame_decoder = self.frame_decoder.take().unwrap_or_default();
+            match StreamingDecoder::new_with_decoder(self.input, frame_decoder) {
+                Ok(decoder) => self.decoder = Some(decoder),
+                // Skippable frames have an 8 byte header of magic and length before their data
+                Err(FrameDecoderError::ReadFrameHeaderError(ReadFrameHeaderError::SkipFrame { length, .. })) => {
+                    self.input = self.input.get(8 + length as usize..).unwrap_or_default();
+                },
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+            }
+        }
+    }
+}
+
+/// Returns a reader decompressing the input as it is read, so large inputs can be counted without holding them in memory.
+/// Concatenated gzip, bzip2, xz and zstd members are read one after another.
+pub fn decoder<'a>(bytes: &'a [u8], compression: Compression) -> Box<dyn Read + 'a> {
+    match compression {
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(bytes)),
+        Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(bytes)),
+        Compression::Xz => Box::new(lzma_rust2::XzReader::new(bytes, true)),
+        Compression::Zstd => Box::new(ZstdFrames { input: bytes, decoder: None, frame_decoder: None }),
+    }
+}
+
+/// Decompresses the full input in memory, for inputs like archives and documents that have to be read as a whole.
+pub fn decompress(bytes: &[u8], compression: Compression) -> io::Result<Vec<u8>> {
+    let mut output = Vec::with_capacity(bytes.len().saturating_mul(4));
+    decoder(bytes, compression).read_to_end(&mut output)?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bzip2_magic_requires_block_magic() {
+        assert_eq!(Compression::from_magic(b"BZhang wrote this"), None);
+        assert_eq!(Compression::from_magic(b"BZh91AY&SY\x00"), Some(Compression::Bzip2));
+        assert_eq!(Compression::from_magic(b"BZh0\x31\x41\x59\x26\x53\x59"), None);
+        assert_eq!(Compression::from_magic(b"BZh9\x17\x72\x45\x38\x50\x90\x00\x00\x00\x00"), Some(Compression::Bzip2));
+    }
+}
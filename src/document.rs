@@ -0,0 +1,281 @@
+use std::io::{self, Cursor, Read, Seek};
+
+use zip::ZipArchive;
+
+use crate::markup::{self, MarkupEvent, MarkupKind, Selector, Tokenizer};
+
+/// Longest run of spaces expanded from a single ODT `text:s` element, so a corrupt or hostile count cannot exhaust memory.
+const MAX_SPACE_RUN: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines the zip based document formats whose text is extracted from the document XML rather than counting the archive members.
+pub enum DocumentKind {
+    Docx,
+    Odt,
+    Epub
+}
+
+impl DocumentKind {
+    /// Identifies the document format of a zip archive from its members, returning `None` for other files and plain zip archives.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if !bytes.starts_with(b"PK\x03\x04") {
+            return None
+        }
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).ok()?;
+        // ODF and EPUB name their format in an uncompressed `mimetype` member
+        let mimetype = read_member(&mut archive, "mimetype").unwrap_or_default();
+        match mimetype.trim() {
+            "application/epub+zip" => Some(Self::Epub),
+            "application/vnd.oasis.opendocument.text" => Some(Self::Odt),
+            _ if archive.index_for_name("word/document.xml").is_some() => Some(Self::Docx),
+            _ => None,
+        }
+    }
+    /// Returns the name of the document format.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Docx => "docx",
+            Self::Odt => "odt",
+            Self::Epub => "epub",
+        }
+    }
+}
+
+/// Reads a member of the archive as UTF-8 text.
+fn read_member<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> io::Result<String> {
+    let mut contents = String::new();
+    archive.by_name(name)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// Extracts the text of the document, returning a single unnamed section for DOCX and ODT or one section per chapter in
+/// reading order named by its path within the book for EPUB.
+pub fn extract(bytes: &[u8], kind: DocumentKind) -> io::Result<Vec<(Option<String>, String)>> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    match kind {
+        DocumentKind::Docx => Ok(vec![(None, extract_docx(&read_member(&mut archive, "word/document.xml")?))]),
+        DocumentKind::Odt => Ok(vec![(None, extract_odt(&read_member(&mut archive, "content.xml")?))]),
+        DocumentKind::Epub => extract_epub(&mut archive),
+    }
+}
+
+/// Extracts the body text of a WordprocessingML document, keeping the text runs of each paragraph on one line.
+/// Deleted revisions and field codes live in other elements than `w:t` so they are not counted, matching Word's own count.
+pub fn extract_docx(document: &str) -> String {
+    let mut text = String::with_capacity(document.len() / 4);
+    let mut is_in_text = false;
+    let mut fallback_depth = 0_usize; // Depth inside `mc:Fallback`, which repeats the content of `mc:Choice` for older readers
+    for event in Tokenizer::new(document, MarkupKind::Xml) {
+        match event {
+            MarkupEvent::Start(element) => match element.name.as_str() {
+                "mc:Fallback" => fallback_depth += 1,
+                "w:t" => is_in_text = true,
+                _ => {},
+            },
+            MarkupEvent::Empty(element) if fallback_depth == 0 => match element.name.as_str() {
+                "w:tab" | "w:ptab" => text.push('\t'),
+                "w:br" | "w:cr" => text.push('\n'),
+                "w:noBreakHyphen" => text.push('-'),
+                _ => {},
+            },
+            MarkupEvent::Empty(_) => {},
+            MarkupEvent::End(name) => match name.as_str() {
+                "mc:Fallback" => fallback_depth = fallback_depth.saturating_sub(1),
+                "w:t" => is_in_text = false,
+                "w:p" if fallback_depth == 0 => text.push('\n'),
+                _ => {},
+            },
+            MarkupEvent::Text(content) if is_in_text && fallback_depth == 0 => text.push_str(&content),
+            MarkupEvent::Text(_) => {},
+        }
+    }
+    text
+}
+
+/// Extracts the body text of an OpenDocument text document, keeping each paragraph and heading on one line.
+/// Footnotes, annotations and tracked deletions are skipped so only the main text is counted.
+pub fn extract_odt(content: &str) -> String {
+    let mut text = String::with_capacity(content.len() / 4);
+    let mut paragraph_depth = 0_usize;
+    let mut skip_depth = 0_usize; // Depth inside skipped elements
+    for event in Tokenizer::new(content, MarkupKind::Xml) {
+        match event {
+            MarkupEvent::Start(element) => match element.name.as_str() {
+                "text:note" | "office:annotation" | "text:tracked-changes" => skip_depth += 1,
+                "text:p" | "text:h" => paragraph_depth += 1,
+                _ => {},
+            },
+            MarkupEvent::Empty(element) if skip_depth == 0 => match element.name.as_str() {
+                // Runs of spaces are stored as a single element with a count
+                "text:s" => text.push_str(&" ".repeat(element.attribute("text:c").and_then(|c| c.parse().ok()).unwrap_or(1).min(MAX_SPACE_RUN))),
+                "text:tab" => text.push('\t'),
+                "text:line-break" => text.push('\n'),
+                _ => {},
+            },
+            MarkupEvent::Empty(_) => {},
+            MarkupEvent::End(name) => match name.as_str() {
+                "text:note" | "office:annotation" | "text:tracked-changes" => skip_depth = skip_depth.saturating_sub(1),
+                "text:p" | "text:h" => {
+                    paragraph_depth = paragraph_depth.saturating_sub(1);
+                    if skip_depth == 0 {
+                        text.push('\n');
+                    }
+                },
+                _ => {},
+            },
+            MarkupEvent::Text(content) if paragraph_depth > 0 && skip_depth == 0 => text.push_str(&content),
+            MarkupEvent::Text(_) => {},
+        }
+    }
+    text
+}
+
+/// Decodes `%XX` escapes in a URL path, leaving invalid escapes as they are.
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%').then(|| path.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok())).flatten();
+        match escaped {
+            Some(byte) => { decoded.push(byte); i += 3; },
+            None => { decoded.push(bytes[i]); i += 1; },
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Resolves a relative `href` against the directory of the file it appears in, normalizing `.` and `..` segments.
+fn resolve_href(base_file: &str, href: &str) -> String {
+    let href = percent_decode(href.split(['#', '?']).next().unwrap_or_default());
+    let mut segments: Vec<&str> = base_file.split('/').collect();
+    segments.pop();
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {},
+            ".." => { segments.pop(); },
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Extracts the text of each XHTML chapter of an EPUB in spine order, locating the package document through `META-INF/container.xml`.
+fn extract_epub<R: Read + Seek>(archive: &mut ZipArchive<R>) -> io::Result<Vec<(Option<String>, String)>> {
+    let container = read_member(archive, "META-INF/container.xml")?;
+    let package_path = Tokenizer::new(&container, MarkupKind::Xml)
+        .find_map(|event| match event {
+            MarkupEvent::Start(element) | MarkupEvent::Empty(element) if element.name == "rootfile" => element.attribute("full-path").map(String::from),
+            _ => None,
+        })
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "container.xml names no package document"))?;
+    let package = read_member(archive, &package_path)?;
+
+    // Map manifest ids to chapter paths, then read them in the order of the spine
+    let mut manifest: Vec<(String, String)> = Vec::new();
+    let mut spine: Vec<String> = Vec::new();
+    for event in Tokenizer::new(&package, MarkupKind::Xml) {
+        let (MarkupEvent::Start(element) | MarkupEvent::Empty(element)) = event else {
+            continue;
+        };
+        // Package documents may use a namespace prefix like `opf:item`
+        match element.name.rsplit(':').next().unwrap_or_default() {
+            "item" => if let (Some(id), Some(href)) = (element.attribute("id"), element.attribute("href")) {
+                manifest.push((id.to_string(), resolve_href(&package_path, href)));
+            },
+            "itemref" => if let Some(idref) = element.attribute("idref") {
+                spine.push(idref.to_string());
+            },
+            _ => {},
+        }
+    }
+
+    let body = Selector::parse("body").map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut chapters = Vec::with_capacity(spine.len());
+    for idref in spine {
+        let Some((_, path)) = manifest.iter().find(|(id, _)| *id == idref) else {
+            continue;
+        };
+        let xhtml = read_member(archive, path)?;
+        // Chapters are XHTML, which the HTML rules handle along with any HTML entities they use
+        chapters.push((Some(path.clone()), markup::extract_text(&xhtml, MarkupKind::Html, Some(&body))));
+    }
+    Ok(chapters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    /// Builds a zip archive with the members stored under the given names.
+    fn zip_archive(members: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in members {
+            writer.start_file(*name, zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored)).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn docx_keeps_paragraph_runs_and_skips_fallbacks() {
+        let document = r#"<w:document><w:body>
+            <w:p><w:r><w:t>Hello</w:t></w:r><w:r><w:t xml:space="preserve"> wor</w:t></w:r><w:r><w:t>ld</w:t><w:tab/><w:t>tabbed</w:t></w:r></w:p>
+            <w:p><w:r><w:t>line</w:t><w:br/><w:t>break</w:t></w:r><w:del><w:r><w:delText>gone</w:delText></w:r></w:del></w:p>
+            <mc:AlternateContent><mc:Choice><w:p><w:r><w:t>shape text</w:t></w:r></w:p></mc:Choice><mc:Fallback><w:p><w:r><w:t>shape text</w:t></w:r></w:p></mc:Fallback></mc:AlternateContent>
+        </w:body></w:document>"#;
+        assert_eq!(extract_docx(document), "Hello world\ttabbed\nline\nbreak\nshape text\n");
+    }
+
+    #[test]
+    fn odt_expands_spaces_and_skips_notes() {
+        let content = r#"<office:text>
+            <text:h>Title</text:h>
+            <text:p>a<text:s text:c="3"/>b<text:s/>c<text:note><text:note-body><text:p>footnote</text:p></text:note-body></text:note></text:p>
+            <text:p>huge<text:s text:c="1000000000000"/>run<text:s text:c="4096"/>end</text:p>
+        </office:text>"#;
+        let text = extract_odt(content);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[..2], ["Title", "a   b c"]);
+        // Implausible counts from corrupt or hostile files are clamped rather than allocated
+        let run = " ".repeat(MAX_SPACE_RUN);
+        assert_eq!(lines[2], format!("huge{run}run{run}end"));
+    }
+
+    #[test]
+    fn hrefs_resolve_against_the_package_directory() {
+        assert_eq!(percent_decode("chapter%201.xhtml"), "chapter 1.xhtml");
+        assert_eq!(percent_decode("100%25%zz%4"), "100%%zz%4");
+        assert_eq!(resolve_href("OEBPS/content.opf", "text/ch1.xhtml#start"), "OEBPS/text/ch1.xhtml");
+        assert_eq!(resolve_href("OEBPS/content.opf", "../Text/./ch%202.xhtml?v=1"), "Text/ch 2.xhtml");
+        assert_eq!(resolve_href("content.opf", "ch1.xhtml"), "ch1.xhtml");
+    }
+
+    #[test]
+    fn epub_chapters_follow_the_spine() {
+        let book = zip_archive(&[
+            ("mimetype", "application/epub+zip"),
+            ("META-INF/container.xml", r#"<container><rootfiles><rootfile full-path="OEBPS/content.opf"/></rootfiles></container>"#),
+            ("OEBPS/content.opf", r#"<package><manifest><opf:item id="b" href="two.xhtml"/><opf:item id="a" href="text/one%20a.xhtml"/></manifest>
+                <spine><itemref idref="a"/><itemref idref="b"/><itemref idref="missing"/></spine></package>"#),
+            ("OEBPS/text/one a.xhtml", "<html><head><title>skipped</title></head><body><p>First chapter</p></body></html>"),
+            ("OEBPS/two.xhtml", "<html><body><p>Second &amp; last</p></body></html>"),
+        ]);
+        assert_eq!(DocumentKind::detect(&book), Some(DocumentKind::Epub));
+        let chapters = extract(&book, DocumentKind::Epub).unwrap();
+        let names: Vec<_> = chapters.iter().map(|(name, _)| name.as_deref().unwrap_or_default()).collect();
+        assert_eq!(names, ["OEBPS/text/one a.xhtml", "OEBPS/two.xhtml"]);
+        assert_eq!(chapters[0].1.split_whitespace().collect::<Vec<_>>(), ["First", "chapter"]);
+        assert_eq!(chapters[1].1.split_whitespace().collect::<Vec<_>>(), ["Second", "&", "last"]);
+    }
+
+    #[test]
+    fn detects_documents_by_their_members() {
+        assert_eq!(DocumentKind::detect(&zip_archive(&[("word/document.xml", "<w:document/>")])), Some(DocumentKind::Docx));
+        assert_eq!(DocumentKind::detect(&zip_archive(&[("mimetype", "application/vnd.oasis.opendocument.text")])), Some(DocumentKind::Odt));
+        assert_eq!(DocumentKind::detect(&zip_archive(&[("notes.txt", "plain")])), None);
+        assert_eq!(DocumentKind::detect(b"plain text"), None);
+    }
+}
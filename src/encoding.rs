@@ -0,0 +1,197 @@
+use std::borrow::Cow;
+
+/// Number of leading bytes inspected for NUL bytes when detecting binary files, similar to the buffer size used by grep.
+pub const BINARY_SNIFF_LEN: usize = 32 * 1024;
+
+/// Code points for bytes `0x80..=0x9F` in Windows-1252, the five undefined bytes map to the matching C1 control as in the WHATWG encoding standard.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines the text encodings that input files can be decoded from.
+pub enum Encoding {
+    Utf8,
+    Latin1,
+    Windows1252,
+    Utf16Le,
+    Utf16Be
+}
+
+impl Encoding {
+    /// Parses an encoding label, accepting common aliases like `iso-8859-1` and `cp1252`.
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label.to_ascii_lowercase().replace('_', "-").as_str() {
+            "utf8" | "utf-8" => Some(Self::Utf8),
+            "latin1" | "latin-1" | "iso-8859-1" => Some(Self::Latin1),
+            "windows-1252" | "cp1252" => Some(Self::Windows1252),
+            "utf-16le" | "utf16le" => Some(Self::Utf16Le),
+            "utf-16be" | "utf16be" => Some(Self::Utf16Be),
+            _ => None,
+        }
+    }
+    /// Returns the canonical label of the encoding.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Latin1 => "latin1",
+            Self::Windows1252 => "windows-1252",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+        }
+    }
+    /// Returns the byte order mark identifying the encoding, if it has one.
+    pub fn bom(&self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => &[0xEF, 0xBB, 0xBF],
+            Self::Utf16Le => &[0xFF, 0xFE],
+            Self::Utf16Be => &[0xFE, 0xFF],
+            Self::Latin1 | Self::Windows1252 => &[],
+        }
+    }
+    /// Returns true for encodings whose text routinely contains NUL bytes, which rules out NUL based binary detection.
+    pub fn is_wide(&self) -> bool {
+        matches!(self, Self::Utf16Le | Self::Utf16Be)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines how files detected as binary are handled, mirroring grep's `--binary-files` option.
+pub enum BinaryMode {
+    Warn, // Skip the file and report it on stderr
+    Skip, // Skip the file silently
+    Text // Count the file as text regardless
+}
+
+#[derive(Debug, Clone)]
+/// Text decoded from raw input bytes, borrowing the bytes directly when they are already valid UTF-8.
+pub struct Decoded<'a> {
+    pub text: Cow<'a, str>,
+    pub encoding: Encoding,
+    pub replaced: usize, // Number of invalid bytes (or UTF-16 code units) replaced with U+FFFD
+    pub has_bom: bool,
+}
+
+/// Detects a UTF-8 or UTF-16 byte order mark at the start of the bytes.
+pub fn detect_bom(bytes: &[u8]) -> Option<Encoding> {
+    [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be].into_iter().find(|e| bytes.starts_with(e.bom()))
+}
+
+/// Returns true if the bytes look binary, i.e. a NUL byte appears within the first `BINARY_SNIFF_LEN` bytes.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0)
+}
+
+/// Returns true if input read as the provided encoding, or the one named by its byte order mark, looks binary.
+/// Wide encodings contain NUL bytes in ordinary text so they are never considered binary.
+pub fn is_binary_input(bytes: &[u8], encoding: Option<Encoding>) -> bool {
+    !encoding.or_else(|| detect_bom(bytes)).is_some_and(|e| e.is_wide()) && is_binary(bytes)
+}
+
+/// Decodes the bytes as the provided encoding, or as the encoding named by a byte order mark falling back to UTF-8 if `None`.
+/// Invalid sequences are replaced with U+FFFD rather than failing, and a byte order mark matching the encoding is stripped.
+pub fn decode(bytes: &[u8], encoding: Option<Encoding>) -> Decoded<'_> {
+    let bom = detect_bom(bytes);
+    let encoding = encoding.or(bom).unwrap_or(Encoding::Utf8);
+    let has_bom = bom == Some(encoding);
+    let bytes = if has_bom { &bytes[encoding.bom().len()..] } else { bytes };
+    let (text, replaced) = match encoding {
+        Encoding::Utf8 => decode_utf8_lossy(bytes),
+        Encoding::Latin1 => (Cow::Owned(bytes.iter().map(|b| *b as char).collect()), 0),
+        Encoding::Windows1252 => (Cow::Owned(bytes.iter().map(|b| if (0x80..=0x9F).contains(b) { WINDOWS_1252_HIGH[(*b - 0x80) as usize] } else { *b as char }).collect()), 0),
+        Encoding::Utf16Le => decode_utf16_lossy(bytes, u16::from_le_bytes),
+        Encoding::Utf16Be => decode_utf16_lossy(bytes, u16::from_be_bytes),
+    };
+    Decoded { text, encoding, replaced, has_bom }
+}
+
+/// Decodes UTF-8, borrowing when valid and otherwise replacing each maximal invalid sequence with U+FFFD while counting the replaced bytes.
+fn decode_utf8_lossy(bytes: &[u8]) -> (Cow<'_, str>, usize) {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return (Cow::Borrowed(text), 0)
+    }
+    let mut text = String::with_capacity(bytes.len());
+    let mut replaced = 0_usize;
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        if !chunk.invalid().is_empty() {
+            text.push(char::REPLACEMENT_CHARACTER);
+            replaced += chunk.invalid().len();
+        }
+    }
+    (Cow::Owned(text), replaced)
+}
+
+/// Decodes UTF-16 code units read with `to_unit`, replacing unpaired surrogates and a trailing odd byte with U+FFFD.
+fn decode_utf16_lossy(bytes: &[u8], to_unit: fn([u8; 2]) -> u16) -> (Cow<'static, str>, usize) {
+    let units = bytes.chunks_exact(2).map(|pair| to_unit([pair[0], pair[1]]));
+    let mut replaced = bytes.len() % 2;
+    let mut text: String = char::decode_utf16(units)
+        .map(|c| c.unwrap_or_else(|_| { replaced += 1; char::REPLACEMENT_CHARACTER }))
+        .collect();
+    if bytes.len() % 2 == 1 {
+        text.push(char::REPLACEMENT_CHARACTER);
+    }
+    (Cow::Owned(text), replaced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_byte_order_marks() {
+        assert_eq!(detect_bom(b"\xEF\xBB\xBFtext"), Some(Encoding::Utf8));
+        assert_eq!(detect_bom(b"\xFF\xFEt\x00"), Some(Encoding::Utf16Le));
+        assert_eq!(detect_bom(b"\xFE\xFF\x00t"), Some(Encoding::Utf16Be));
+        assert_eq!(detect_bom(b"text"), None);
+        assert_eq!(detect_bom(b""), None);
+    }
+
+    #[test]
+    fn strips_the_bom_of_the_detected_encoding() {
+        let decoded = decode(b"\xEF\xBB\xBFcaf\xC3\xA9", None);
+        assert_eq!((decoded.text.as_ref(), decoded.encoding, decoded.has_bom, decoded.replaced), ("café", Encoding::Utf8, true, 0));
+        assert!(matches!(decode(b"plain", None).text, Cow::Borrowed("plain")));
+        let decoded = decode(b"\xFE\xFF\x00h\x00i\xD8\x3D\xDE\x00", None);
+        assert_eq!((decoded.text.as_ref(), decoded.encoding, decoded.has_bom), ("hi😀", Encoding::Utf16Be, true));
+    }
+
+    #[test]
+    fn explicit_encodings_override_the_bom() {
+        // A UTF-8 BOM read as Latin-1 is three ordinary characters rather than a mark to strip
+        let decoded = decode(b"\xEF\xBB\xBFa\xE9", Some(Encoding::Latin1));
+        assert_eq!((decoded.text.as_ref(), decoded.has_bom), ("\u{EF}\u{BB}\u{BF}aé", false));
+        let decoded = decode(b"\x93quoted\x94 \x80 \x81", Some(Encoding::Windows1252));
+        assert_eq!(decoded.text, "\u{201C}quoted\u{201D} \u{20AC} \u{0081}");
+        assert_eq!(decode(b"h\x00i\x00", Some(Encoding::Utf16Le)).text, "hi");
+        assert_eq!(Encoding::from_label("ISO_8859-1"), Some(Encoding::Latin1));
+        assert_eq!(Encoding::from_label("CP1252"), Some(Encoding::Windows1252));
+        assert_eq!(Encoding::from_label("ebcdic"), None);
+    }
+
+    #[test]
+    fn counts_replaced_bytes() {
+        let decoded = decode(b"ok\xFF\xFEok\xC3", None);
+        assert_eq!((decoded.text.as_ref(), decoded.replaced), ("ok\u{FFFD}\u{FFFD}ok\u{FFFD}", 3));
+        // An unpaired surrogate and a trailing odd byte in UTF-16
+        let decoded = decode(b"\x00\xD8a\x00b", Some(Encoding::Utf16Le));
+        assert_eq!((decoded.text.as_ref(), decoded.replaced), ("\u{FFFD}a\u{FFFD}", 2));
+    }
+
+    #[test]
+    fn binary_detection_sniffs_for_nul_bytes() {
+        assert!(is_binary_input(b"\x7FELF\x02\x01\x00\x00", None));
+        assert!(!is_binary_input(b"plain text\n", None));
+        let mut late_nul = vec![b'a'; BINARY_SNIFF_LEN];
+        late_nul.push(0);
+        assert!(!is_binary_input(&late_nul, None));
+        // UTF-16 text is full of NUL bytes but is not binary, whether named by its BOM or explicitly
+        assert!(!is_binary_input(b"\xFF\xFEh\x00i\x00", None));
+        assert!(!is_binary_input(b"h\x00i\x00", Some(Encoding::Utf16Le)));
+        assert!(is_binary_input(b"h\x00i\x00", Some(Encoding::Utf8)));
+    }
+}
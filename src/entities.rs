@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+
+use crate::{Table, TableStyle};
+
+/// Month names and their common abbreviations, recognized in dates like `5 January 2024` or `Jan 5, 2024`.
+const MONTHS: [&str; 24] = [
+    "january", "february", "march", "april", "may", "june", "july", "august", "september", "october", "november", "december",
+    "jan", "feb", "mar", "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov", "dec",
+];
+
+/// Characters trimmed from the start of a token before recognizing it, like opening brackets and quotes.
+const LEADING_PUNCTUATION: [char; 8] = ['(', '[', '{', '"', '\'', '<', '“', '‘'];
+
+/// Characters trimmed from the end of a token before recognizing it, like sentence punctuation and closing brackets.
+const TRAILING_PUNCTUATION: [char; 14] = ['.', ',', ';', ':', '!', '?', ')', ']', '}', '"', '\'', '>', '”', '’'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Defines the kinds of entities recognized ahead of word counting.
+pub enum EntityKind {
+    Url,
+    Email,
+    Mention,
+    Hashtag,
+    Date,
+    Number,
+    Emoji
+}
+
+impl EntityKind {
+    /// Every kind of entity, in the order their tables are displayed.
+    pub const ALL: [EntityKind; 7] = [Self::Url, Self::Email, Self::Mention, Self::Hashtag, Self::Date, Self::Number, Self::Emoji];
+
+    /// Parses a plural entity label like `urls`.
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.label() == label)
+    }
+    /// Returns the plural label of the entity kind, used for table headers and option values.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Url => "urls",
+            Self::Email => "emails",
+            Self::Mention => "mentions",
+            Self::Hashtag => "hashtags",
+            Self::Date => "dates",
+            Self::Number => "numbers",
+            Self::Emoji => "emoji",
+        }
+    }
+    /// Returns the singular label of the entity kind, used for table columns.
+    fn singular(&self) -> &'static str {
+        match self {
+            Self::Url => "url",
+            Self::Email => "email",
+            Self::Mention => "mention",
+            Self::Hashtag => "hashtag",
+            Self::Date => "date",
+            Self::Number => "number",
+            Self::Emoji => "emoji",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Occurrences of each distinct entity by kind, merged across inputs.
+pub struct EntityCounts {
+    counts: HashMap<EntityKind, HashMap<String, usize>>,
+}
+
+impl EntityCounts {
+    /// Records one occurrence of an entity.
+    pub fn add(&mut self, kind: EntityKind, entity: &str) {
+        *self.counts.entry(kind).or_default().entry(entity.to_string()).or_insert(0) += 1;
+    }
+    /// Returns the total occurrences of entities of the kind.
+    pub fn total(&self, kind: EntityKind) -> usize {
+        self.counts.get(&kind).map_or(0, |entities| entities.values().sum())
+    }
+    /// Returns the distinct entities of the kind sorted by descending count, then alphabetically.
+    pub fn ranked(&self, kind: EntityKind) -> Vec<(&str, usize)> {
+        let mut ranked: Vec<(&str, usize)> = self.counts.get(&kind).map_or_else(Vec::new, |entities| entities.iter().map(|(e, c)| (e.as_str(), *c)).collect());
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        ranked
+    }
+}
+
+/// Returns true for characters that start an emoji: pictographs, symbols and dingbats presented as emoji, and regional indicators of flags.
+fn is_emoji_base(c: char) -> bool {
+    matches!(c as u32, 0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B50 | 0x2B55 | 0x2B05..=0x2B07 | 0x2B1B | 0x2B1C | 0x231A | 0x231B
+        | 0x2328 | 0x23CF | 0x23E9..=0x23FA | 0x3030 | 0x303D | 0x3297 | 0x3299)
+}
+
+/// Returns true for characters that continue an emoji sequence: variation selectors, skin tone modifiers, keycaps and tag characters.
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(c as u32, 0xFE0E | 0xFE0F | 0x1F3FB..=0x1F3FF | 0x20E3 | 0xE0020..=0xE007F)
+}
+
+/// Removes emoji sequences from the text, recording each one. Sequences joined with zero width joiners, modified with skin tones,
+/// keycaps like `1️⃣` and flags made of regional indicator pairs count as a single emoji.
+fn extract_emoji(text: &str, counts: &mut EntityCounts) -> String {
+    let mut remaining = String::with_capacity(text.len());
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        // Keycaps are a digit, `#` or `*` followed by the keycap mark, usually with a variation selector between them
+        let is_keycap = (c.is_ascii_digit() || c == '#' || c == '*') && {
+            let rest = &text[start + 1..];
+            rest.starts_with('\u{20E3}') || rest.starts_with("\u{FE0F}\u{20E3}")
+        };
+        if !is_emoji_base(c) && !is_keycap {
+            remaining.push(c);
+            continue;
+        }
+        let mut end = start + c.len_utf8();
+        let is_regional_indicator = |c: char| (0x1F1E6..=0x1F1FF).contains(&(c as u32));
+        if is_regional_indicator(c) {
+            if let Some((i, next)) = chars.next_if(|(_, next)| is_regional_indicator(*next)) {
+                end = i + next.len_utf8();
+            }
+        }
+        loop {
+            if let Some((i, modifier)) = chars.next_if(|(_, next)| is_emoji_modifier(*next)) {
+                end = i + modifier.len_utf8();
+            } else if text[end..].starts_with('\u{200D}') && text[end + 3..].chars().next().is_some_and(is_emoji_base) {
+                chars.next();
+                let (i, joined) = chars.next().unwrap_or_default();
+                end = i + joined.len_utf8();
+            } else {
+                break;
+            }
+        }
+        counts.add(EntityKind::Emoji, &text[start..end]);
+        remaining.push(' ');
+    }
+    remaining
+}
+
+/// Returns true if the token is a URL with a scheme like `https://` or starting with `www.`.
+fn is_url(token: &str) -> bool {
+    let lower = token.to_ascii_lowercase();
+    ["http://", "https://", "ftp://", "www."].iter().any(|prefix| lower.starts_with(prefix) && lower.len() > prefix.len())
+}
+
+/// Returns true if the token is an email address, i.e. a local part and a dotted domain of letters, digits and hyphens separated by one `@`.
+fn is_email(token: &str) -> bool {
+    let Some((local, domain)) = token.split_once('@') else {
+        return false
+    };
+    let is_local_char = |c: char| c.is_alphanumeric() || "._%+-'".contains(c);
+    let labels: Vec<&str> = domain.split('.').collect();
+    !local.is_empty() && local.chars().all(is_local_char) && labels.len() > 1
+        && labels.iter().all(|l| !l.is_empty() && l.chars().all(|c| c.is_alphanumeric() || c == '-'))
+        && labels.last().is_some_and(|tld| tld.chars().all(char::is_alphabetic))
+}
+
+/// Returns true if the token is the prefix followed by a name of letters, digits and underscores containing at least one letter.
+fn is_tag(token: &str, prefix: char) -> bool {
+    token.strip_prefix(prefix).is_some_and(|name| {
+        !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') && name.chars().any(char::is_alphabetic)
+    })
+}
+
+/// Returns true if the token is a number like `42`, `-3.5`, `1,234,567`, `42%` or `$9.99`.
+fn is_number(token: &str) -> bool {
+    let token = token.strip_prefix(['$', '€', '£', '¥']).unwrap_or(token);
+    let token = token.strip_prefix(['-', '+', '−']).unwrap_or(token);
+    let token = token.strip_suffix('%').unwrap_or(token);
+    let (integer, fraction) = token.split_once('.').unwrap_or((token, "0"));
+    let groups: Vec<&str> = integer.split(',').collect();
+    // Thousands separators must group digits by three
+    let is_grouped = groups.len() == 1 || (groups[0].len() <= 3 && groups[1..].iter().all(|g| g.len() == 3));
+    !integer.is_empty() && is_grouped && groups.iter().all(|g| !g.is_empty() && g.bytes().all(|b| b.is_ascii_digit()))
+        && !fraction.is_empty() && fraction.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Returns true if the token is a numeric date like `2024-01-31`, `2024-01-31T09:30:00Z`, `31/01/2024`, `1/31/24` or `31.01.2024`.
+fn is_numeric_date(token: &str) -> bool {
+    let date = token.split_once('T').map_or(token, |(date, _)| date);
+    for separator in ['-', '/', '.'] {
+        let parts: Vec<&str> = date.split(separator).collect();
+        if parts.len() != 3 || !parts.iter().all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit())) {
+            continue;
+        }
+        let lengths: Vec<usize> = parts.iter().map(|p| p.len()).collect();
+        let is_year_first = lengths[0] == 4 && lengths[1] <= 2 && lengths[2] <= 2;
+        let is_year_last = lengths[0] <= 2 && lengths[1] <= 2 && (lengths[2] == 4 || lengths[2] == 2);
+        // ISO dates use hyphens, and dotted or slashed two digit years are ambiguous with versions unless the year comes last
+        if (is_year_first && separator == '-') || (is_year_last && separator != '-') || (is_year_first && separator == '/') {
+            let values: Vec<u32> = parts.iter().map(|p| p.parse().unwrap_or_default()).collect();
+            let (a, b) = if is_year_first { (values[1], values[2]) } else { (values[0], values[1]) };
+            // Either order of day and month is accepted as long as one of them can be the month
+            return (1..=12).contains(&a) && (1..=31).contains(&b) || (1..=31).contains(&a) && (1..=12).contains(&b)
+        }
+    }
+    false
+}
+
+/// Returns true if the token names a month.
+fn is_month(token: &str) -> bool {
+    MONTHS.contains(&token.trim_end_matches('.').to_lowercase().as_str())
+}
+
+/// Returns true if the token is a day of the month, optionally with an ordinal suffix like `5th`.
+fn is_day(token: &str) -> bool {
+    let digits = token.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &token[digits.len()..];
+    ["", "st", "nd", "rd", "th"].contains(&suffix.to_ascii_lowercase().as_str()) && digits.parse::<u32>().is_ok_and(|d| (1..=31).contains(&d))
+}
+
+/// Returns true if the token is a four digit year.
+fn is_year(token: &str) -> bool {
+    token.len() == 4 && token.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Returns the number of tokens starting at `tokens` forming a written date like `January 5, 2024`, `5 Jan 2024` or `5th of March 2024`.
+fn written_date_len(tokens: &[&str]) -> usize {
+    let core = |i: usize| tokens.get(i).map_or("", |t| trim_token(t).1);
+    if is_month(core(0)) && is_day(core(1)) && is_year(core(2)) {
+        3
+    } else if is_day(core(0)) && core(1).eq_ignore_ascii_case("of") && is_month(core(2)) && is_year(core(3)) {
+        4
+    } else if is_day(core(0)) && is_month(core(1)) && is_year(core(2)) {
+        3
+    } else {
+        0
+    }
+}
+
+/// Splits a token into its leading punctuation, its core and its trailing punctuation. A closing parenthesis is kept when the core
+/// opens one, so URLs like `https://en.wikipedia.org/wiki/Rust_(language)` stay whole.
+fn trim_token(token: &str) -> (&str, &str, &str) {
+    let core = token.trim_start_matches(LEADING_PUNCTUATION);
+    let start = token.len() - core.len();
+    let mut end = token.len();
+    while let Some(c) = token[start..end].chars().next_back().filter(|c| TRAILING_PUNCTUATION.contains(c)) {
+        if c == ')' && token[start..end].matches('(').count() >= token[start..end].matches(')').count() {
+            break;
+        }
+        end -= c.len_utf8();
+    }
+    (&token[..start], &token[start..end], &token[end..])
+}
+
+/// Recognizes a single entity token, returning its kind and its normalized form.
+fn recognize(core: &str, kinds: &[EntityKind], is_cased: bool) -> Option<(EntityKind, String)> {
+    let fold = |s: &str| if is_cased { s.to_string() } else { s.to_lowercase() };
+    let kind = if is_url(core) {
+        EntityKind::Url
+    } else if is_email(core) {
+        EntityKind::Email
+    } else if is_tag(core, '@') {
+        EntityKind::Mention
+    } else if is_tag(core, '#') {
+        EntityKind::Hashtag
+    } else if is_numeric_date(core) {
+        EntityKind::Date
+    } else if is_number(core) {
+        EntityKind::Number
+    } else {
+        return None
+    };
+    // URLs keep their case since paths are case sensitive
+    kinds.contains(&kind).then(|| (kind, if kind == EntityKind::Url || kind == EntityKind::Number { core.to_string() } else { fold(core) }))
+}
+
+/// Pulls the entities of the selected kinds out of the text before it is tokenized into words, recording them in `counts`.
+/// Returns the text with each recognized entity replaced by a space and its surrounding punctuation kept, so it is not counted as a word
+/// too while every line stays in place.
+/// Entities are recognized per whitespace separated token after trimming punctuation, except emoji which are found anywhere and
+/// written dates like `January 5, 2024` which span several tokens.
+pub fn extract(text: &str, kinds: &[EntityKind], is_cased: bool, counts: &mut EntityCounts) -> String {
+    let text = if kinds.contains(&EntityKind::Emoji) { extract_emoji(text, counts) } else { text.to_string() };
+    let mut remaining = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let offset = |token: &str| token.as_ptr() as usize - line.as_ptr() as usize;
+        let mut copied = 0; // Offset in the line up to which it has been copied to the remaining text
+        let mut i = 0;
+        while i < tokens.len() {
+            let date_len = if kinds.contains(&EntityKind::Date) { written_date_len(&tokens[i..]) } else { 0 };
+            // Byte range of the entity within the line, replaced by a single space
+            let span = if date_len > 0 {
+                let words: Vec<&str> = tokens[i..i + date_len].iter().map(|t| trim_token(t).1).collect();
+                counts.add(EntityKind::Date, &words.join(" ").to_lowercase());
+                let last = tokens[i + date_len - 1];
+                Some((offset(tokens[i]) + trim_token(tokens[i]).0.len(), offset(last) + last.len() - trim_token(last).2.len()))
+            } else {
+                let (leading, core, _) = trim_token(tokens[i]);
+                recognize(core, kinds, is_cased).map(|(kind, entity)| {
+                    counts.add(kind, &entity);
+                    let start = offset(tokens[i]) + leading.len();
+                    (start, start + core.len())
+                })
+            };
+            if let Some((start, end)) = span {
+                remaining.push_str(&line[copied..start]);
+                remaining.push(' ');
+                copied = end;
+            }
+            i += date_len.max(1);
+        }
+        remaining.push_str(&line[copied..]);
+    }
+    remaining
+}
+
+/// Create a table of the `top_n` most frequent entities of the kind with their counts, or `None` if none were found.
+pub fn generate_entity_table(counts: &EntityCounts, kind: EntityKind, top_n: usize, style: TableStyle) -> Option<Table<String, String>> {
+    let ranked = counts.ranked(kind);
+    if ranked.is_empty() {
+        return None
+    }
+    let headers = vec!["#", kind.singular(), "ct"].into_iter().map(String::from).collect();
+    let data = ranked.into_iter().take(top_n).enumerate()
+        .map(|(i, (entity, count))| vec![(i + 1).to_string(), entity.to_string(), count.to_string()])
+        .collect();
+    Some(Table::new_with_style(headers, data, style, "><>"))
+}
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+
+use crate::{Table, TableStyle};
+
+/// Maximum width in characters of the bars drawn next to each bucket count.
+const BAR_WIDTH: usize = 30;
+/// Maximum number of rows in the rendered table, longer lengths are grouped into a final open-ended bucket.
+const MAX_ROWS: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines what is being measured when building a length `Histogram`.
+pub enum HistogramKind {
+    Words,
+    Lines
+}
+
+impl HistogramKind {
+    /// Returns the default bucket width for the kind, words are short enough to bucket individually while lines are grouped by tens.
+    pub fn default_bucket_width(&self) -> usize {
+        match self {
+            Self::Words => 1,
+            Self::Lines => 10,
+        }
+    }
+    /// Returns the header label used for the bucket column.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Words => "word len",
+            Self::Lines => "line len",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Counts of lengths (in characters) grouped into fixed-width buckets for rendering as a horizontal bar chart.
+pub struct Histogram {
+    pub kind: HistogramKind,
+    pub bucket_width: usize,
+    pub buckets: BTreeMap<usize, usize>, // Maps the lower bound of each bucket to the number of lengths falling within it
+}
+
+impl Histogram {
+    /// Create an empty `Histogram` of the specified kind using its default bucket width.
+    pub fn new(kind: HistogramKind) -> Self {
+        Histogram { kind, bucket_width: kind.default_bucket_width(), buckets: BTreeMap::new() }
+    }
+    /// Add a single length measurement to the appropriate bucket.
+    pub fn add(&mut self, length: usize) {
+        self.add_n(length, 1);
+    }
+    /// Add `n` measurements of the same length to the appropriate bucket.
+    pub fn add_n(&mut self, length: usize, n: usize) {
+        let lower = (length / self.bucket_width) * self.bucket_width;
+        *self.buckets.entry(lower).or_insert(0) += n;
+    }
+    /// Returns the total number of measurements across all buckets.
+    pub fn total(&self) -> usize {
+        self.buckets.values().sum()
+    }
+    /// Returns the display label for the bucket starting at `lower`, either the length itself or an inclusive range.
+    fn bucket_label(&self, lower: usize) -> String {
+        if self.bucket_width == 1 {
+            lower.to_string()
+        } else {
+            format!("{}-{}", lower, lower + self.bucket_width - 1)
+        }
+    }
+    /// Converts the histogram into a `Table` with bucket, count and bar columns, filling in empty buckets between the shortest and longest lengths.
+    /// At most `MAX_ROWS` rows are shown, so a single very long token or line adds one `≥N` row rather than thousands of empty ones.
+    pub fn to_table(&self, style: TableStyle) -> Table<String, String> {
+        let headers = vec![self.kind.label().to_string(), "ct".to_string()];
+        let mut data: Vec<Vec<String>> = Vec::new();
+        if let (Some(first), Some(last)) = (self.buckets.keys().next(), self.buckets.keys().next_back()) {
+            let overflow = first + (MAX_ROWS - 1) * self.bucket_width; // Lower bound of the open-ended bucket
+            let end = if *last < overflow { *last } else { overflow - 1 };
+            data.extend((*first..=end).step_by(self.bucket_width)
+                .map(|lower| vec![self.bucket_label(lower), self.buckets.get(&lower).copied().unwrap_or(0).to_string()]));
+            if *last >= overflow {
+                data.push(vec![format!("≥{overflow}"), self.buckets.range(overflow..).map(|(_, n)| n).sum::<usize>().to_string()]);
+            }
+        }
+        let mut table = Table::new_with_style(headers, data, style, ">>");
+        table.add_bar_column(1, BAR_WIDTH);
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the bucket and count columns of the rendered table.
+    fn rows(hist: &Histogram) -> Vec<(String, String)> {
+        hist.to_table(TableStyle::Polars).data.into_iter().map(|row| (row[0].clone(), row[1].clone())).collect()
+    }
+
+    #[test]
+    fn lengths_fall_into_lower_bounds() {
+        let mut hist = Histogram::new(HistogramKind::Lines);
+        [0, 9, 10, 19, 25].into_iter().for_each(|length| hist.add(length));
+        hist.add_n(12, 3);
+        assert_eq!(hist.buckets.into_iter().collect::<Vec<_>>(), [(0, 2), (10, 5), (20, 1)]);
+    }
+
+    #[test]
+    fn table_fills_gaps_between_lengths() {
+        let mut hist = Histogram::new(HistogramKind::Lines);
+        hist.add_n(15, 2);
+        hist.add(42);
+        let expected = [("10-19", "2"), ("20-29", "0"), ("30-39", "0"), ("40-49", "1")];
+        assert_eq!(rows(&hist), expected.map(|(b, n)| (b.to_string(), n.to_string())));
+        assert_eq!(hist.total(), 3);
+    }
+
+    #[test]
+    fn long_lengths_share_an_overflow_row() {
+        let mut hist = Histogram::new(HistogramKind::Words);
+        hist.add_n(3, 10);
+        hist.add(MAX_ROWS + 2);
+        hist.add(5000);
+        let rows = rows(&hist);
+        assert_eq!(rows.len(), MAX_ROWS);
+        assert_eq!(rows[0], ("3".to_string(), "10".to_string()));
+        assert_eq!(rows[MAX_ROWS - 1], (format!("≥{}", 3 + MAX_ROWS - 1), "2".to_string()));
+    }
+}
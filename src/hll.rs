@@ -0,0 +1,92 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Default precision used by `HyperLogLog`, giving 16,384 registers (16 KiB) and a standard error of ~0.81%.
+pub const DEFAULT_PRECISION: u8 = 14;
+/// Smallest supported precision.
+pub const MIN_PRECISION: u8 = 4;
+/// Largest supported precision.
+pub const MAX_PRECISION: u8 = 18;
+
+#[derive(Debug, Clone)]
+/// HyperLogLog cardinality estimator for counting unique words in constant memory of `2^precision` bytes.
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>, // Maximum observed rank of the hashed words falling in each register
+}
+
+impl HyperLogLog {
+    /// Create an empty estimator, clamping `precision` to the supported range.
+    pub fn new(precision: u8) -> Self {
+        let precision = precision.clamp(MIN_PRECISION, MAX_PRECISION);
+        HyperLogLog { precision, registers: vec![0; 1 << precision] }
+    }
+    /// Returns the precision, i.e. the number of hash bits used to select a register.
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+    /// Returns the memory used by the registers in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        self.registers.len()
+    }
+    /// Returns the relative standard error of the estimate, `1.04 / sqrt(m)` for `m` registers.
+    pub fn standard_error(&self) -> f64 {
+        1.04 / (self.registers.len() as f64).sqrt()
+    }
+    /// Add a value to the estimator, repeated values have no effect on the estimate.
+    pub fn insert<T: Hash + ?Sized>(&mut self, value: &T) {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let hash = hasher.finish();
+        // The leading `precision` bits choose the register and the rank is taken from the remaining bits
+        let index = (hash >> (64 - self.precision)) as usize;
+        let remaining = hash << self.precision;
+        let rank = (remaining.leading_zeros() as u8).min(64 - self.precision) + 1;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+    /// Returns the estimated number of distinct values inserted, applying linear counting for small cardinalities.
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self.registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+        let raw = alpha * m * m / sum;
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_within_error_bound() {
+        for (precision, cardinality) in [(DEFAULT_PRECISION, 100_000), (10, 50_000), (DEFAULT_PRECISION, 500)] {
+            let mut hll = HyperLogLog::new(precision);
+            for i in 0..cardinality {
+                hll.insert(&format!("word{}", i));
+                hll.insert(&format!("word{}", i / 2)); // Repeats must not change the estimate
+            }
+            // Four standard errors makes a spurious failure practically impossible while still catching a broken estimator
+            let relative_error = (hll.estimate() - cardinality as f64).abs() / cardinality as f64;
+            assert!(relative_error < 4.0 * hll.standard_error(), "precision {} cardinality {} error {}", precision, cardinality, relative_error);
+        }
+    }
+
+    #[test]
+    fn precision_is_clamped() {
+        assert_eq!(HyperLogLog::new(0).precision(), MIN_PRECISION);
+        assert_eq!(HyperLogLog::new(30).memory_bytes(), 1 << MAX_PRECISION);
+    }
+}
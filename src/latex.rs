@@ -0,0 +1,323 @@
+use crate::{Table, TableStyle};
+
+/// Sectioning commands in order of depth, whose titles are counted as headers and start a new section.
+const SECTION_COMMANDS: [&str; 7] = ["part", "chapter", "section", "subsection", "subsubsection", "paragraph", "subparagraph"];
+
+/// Commands whose required arguments are labels, keys, paths or lengths rather than text, with the number of arguments skipped.
+const SKIPPED_ARGUMENT_COMMANDS: [(&str, usize); 33] = [
+    ("label", 1), ("ref", 1), ("eqref", 1), ("pageref", 1), ("autoref", 1), ("cref", 1), ("Cref", 1), ("cite", 1),
+    ("citep", 1), ("citet", 1), ("citeauthor", 1), ("citeyear", 1), ("nocite", 1), ("includegraphics", 1), ("input", 1), ("include", 1),
+    ("bibliography", 1), ("bibliographystyle", 1), ("url", 1), ("href", 1), ("vspace", 1), ("hspace", 1), ("setlength", 2), ("addtolength", 2),
+    ("setcounter", 2), ("addtocounter", 2), ("newcommand", 2), ("renewcommand", 2), ("newenvironment", 3), ("usepackage", 1), ("documentclass", 1), ("pagestyle", 1),
+    ("thispagestyle", 1),
+];
+
+/// Environments typeset as displayed math, whose content is counted as a single formula.
+const DISPLAY_MATH_ENVIRONMENTS: [&str; 14] = ["equation", "equation*", "align", "align*", "alignat", "alignat*", "gather", "gather*", "multline", "multline*", "flalign", "flalign*", "eqnarray", "displaymath"];
+
+/// Environments whose content is not prose, such as code listings and drawings, which are skipped entirely.
+const SKIPPED_ENVIRONMENTS: [&str; 8] = ["verbatim", "verbatim*", "lstlisting", "minted", "comment", "tikzpicture", "thebibliography", "filecontents"];
+
+/// Environments taking required arguments after `\begin{...}` that are layout rather than text, with the number of arguments skipped.
+const ENVIRONMENT_ARGUMENTS: [(&str, usize); 5] = [("tabular", 1), ("tabular*", 2), ("tabularx", 2), ("array", 1), ("minipage", 1)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines the category words are counted in, mirroring texcount.
+enum Target {
+    Text,
+    Header,
+    Caption
+}
+
+#[derive(Debug, Clone, Default)]
+/// Word and formula counts of a single section, where the words before the first sectioning command form an untitled section.
+pub struct LatexSection {
+    pub command: String, // Sectioning command like `section`, empty for the untitled leading section
+    pub title: String,
+    pub text_words: usize,
+    pub header_words: usize,
+    pub caption_words: usize,
+    pub inline_math: usize,
+    pub display_math: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+/// The countable text of a LaTeX document along with per-section counts.
+pub struct LatexDocument {
+    pub text: String, // Text, headers and captions of the document body with commands, math and comments removed
+    pub sections: Vec<LatexSection>,
+}
+
+impl LatexDocument {
+    /// Returns the counts summed across all sections.
+    pub fn totals(&self) -> LatexSection {
+        self.sections.iter().fold(LatexSection::default(), |mut total, s| {
+            total.text_words += s.text_words;
+            total.header_words += s.header_words;
+            total.caption_words += s.caption_words;
+            total.inline_math += s.inline_math;
+            total.display_math += s.display_math;
+            total
+        })
+    }
+}
+
+/// Returns the byte length of the brace delimited group at the start of `text` including both braces, or of the rest of the text if unbalanced.
+fn group_len(text: &str, open: char, close: char) -> usize {
+    let mut depth = 0_usize;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == open => depth += 1,
+            c if c == close => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return i + c.len_utf8()
+                }
+            },
+            _ => {},
+        }
+    }
+    text.len()
+}
+
+/// Returns the byte length of the optional `[...]` arguments and any `*` directly at the start of `text`.
+fn optional_arguments_len(text: &str) -> usize {
+    let mut len = if text.starts_with('*') { 1 } else { 0 };
+    while text[len..].starts_with('[') {
+        len += group_len(&text[len..], '[', ']');
+    }
+    len
+}
+
+/// Returns the byte length of `count` required `{...}` arguments at the start of `text`, including whitespace between them.
+fn required_arguments_len(text: &str, count: usize) -> usize {
+    let mut len = 0;
+    for _ in 0..count {
+        let start = len + (text[len..].len() - text[len..].trim_start().len());
+        if !text[start..].starts_with('{') {
+            break;
+        }
+        len = start + group_len(&text[start..], '{', '}');
+    }
+    len
+}
+
+/// Returns the byte offset of the first unescaped occurrence of `terminator` in `text`.
+fn find_unescaped(text: &str, terminator: &str) -> Option<usize> {
+    let mut i = 0;
+    while i < text.len() {
+        if text[i..].starts_with('\\') && !terminator.starts_with('\\') {
+            i += 1 + text[i + 1..].chars().next().map_or(0, char::len_utf8);
+        } else if text[i..].starts_with(terminator) {
+            return Some(i)
+        } else {
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    None
+}
+
+/// Extracts the countable text of a LaTeX document and counts words per section like texcount. The preamble, comments, math and the arguments
+/// of reference, citation and layout commands are skipped, while the arguments of sectioning commands and captions are counted as headers and captions.
+pub fn parse(source: &str) -> LatexDocument {
+    // Only the document body is counted when the source has one
+    let body_start = source.find("\\begin{document}").map_or(0, |i| i + "\\begin{document}".len());
+    let body_end = source.find("\\end{document}").filter(|end| *end >= body_start).unwrap_or(source.len());
+    let body = &source[body_start..body_end];
+
+    let mut document = LatexDocument::default();
+    let mut section = LatexSection::default();
+    let mut buffers: [String; 3] = Default::default(); // Text, header and caption words of the current section
+    let mut targets: Vec<Target> = vec![Target::Text]; // Category of each open brace group, the last one is current
+    let mut pending_target: Option<Target> = None; // Category of the next brace group, set by sectioning commands and captions
+    let close_section = |section: &mut LatexSection, buffers: &mut [String; 3], document: &mut LatexDocument| {
+        // Like texcount, stray punctuation left between skipped commands is not a word
+        let count = |buffer: &str| buffer.split_whitespace().filter(|w| w.chars().any(char::is_alphanumeric)).count();
+        section.text_words = count(&buffers[0]);
+        section.header_words = count(&buffers[1]);
+        section.caption_words = count(&buffers[2]);
+        section.title = buffers[1].split_whitespace().collect::<Vec<_>>().join(" ");
+        if !section.command.is_empty() || section.text_words + section.caption_words + section.inline_math + section.display_math > 0 {
+            document.sections.push(std::mem::take(section));
+        }
+        buffers.iter_mut().for_each(String::clear);
+    };
+
+    let mut pos = 0;
+    while pos < body.len() {
+        let rest = &body[pos..];
+        let c = rest.chars().next().unwrap_or_default();
+        let target = targets.last().copied().unwrap_or(Target::Text);
+        // Each branch sets the text emitted for the current category, if any, and the number of bytes consumed
+        let (emitted, consumed): (Option<char>, usize) = match c {
+            '%' => (None, rest.find('\n').unwrap_or(rest.len())),
+            '{' => {
+                targets.push(pending_target.take().unwrap_or(target));
+                (None, 1)
+            },
+            '}' => {
+                let is_header_end = targets.len() > 1 && targets.pop() == Some(Target::Header);
+                (is_header_end.then_some('\n'), 1)
+            },
+            '~' => (Some(' '), 1),
+            '$' => {
+                let delimiter = if rest.starts_with("$$") { "$$" } else { "$" };
+                if delimiter == "$$" { section.display_math += 1 } else { section.inline_math += 1 }
+                (None, find_unescaped(&rest[delimiter.len()..], delimiter).map_or(rest.len(), |e| e + 2 * delimiter.len()))
+            },
+            '\\' => {
+                let after = &rest[1..];
+                let name_len = after.find(|ch: char| !ch.is_ascii_alphabetic()).unwrap_or(after.len());
+                if name_len == 0 {
+                    // Control symbols: escaped characters, line breaks, spacing, math delimiters and accents
+                    let symbol = after.chars().next().unwrap_or_default();
+                    let consumed = 1 + symbol.len_utf8();
+                    match symbol {
+                        '[' | '(' => {
+                            let close = if symbol == '[' { "\\]" } else { "\\)" };
+                            if symbol == '[' { section.display_math += 1 } else { section.inline_math += 1 }
+                            (None, find_unescaped(&rest[2..], close).map_or(rest.len(), |e| e + 2 + close.len()))
+                        },
+                        '\\' => (Some('\n'), consumed + optional_arguments_len(&rest[consumed..])),
+                        '%' | '&' | '$' | '#' | '_' | '{' | '}' => (Some(symbol), consumed),
+                        ' ' | ',' | ';' | ':' | '!' | '>' => (Some(' '), consumed),
+                        // Accents like `\'e` leave the accented letter to continue the word
+                        _ => (None, consumed),
+                    }
+                } else {
+                    let name = &after[..name_len];
+                    let args = &rest[1 + name_len..];
+                    let mut consumed = 1 + name_len;
+                    let mut emitted = None;
+                    if let Some(depth) = SECTION_COMMANDS.iter().position(|s| *s == name) {
+                        close_section(&mut section, &mut buffers, &mut document);
+                        section.command = SECTION_COMMANDS[depth].to_string();
+                        consumed += optional_arguments_len(args);
+                        pending_target = Some(Target::Header);
+                        emitted = Some('\n');
+                    } else if name == "caption" {
+                        consumed += optional_arguments_len(args);
+                        pending_target = Some(Target::Caption);
+                    } else if let Some((_, count)) = SKIPPED_ARGUMENT_COMMANDS.iter().find(|(command, _)| *command == name) {
+                        let optional_len = optional_arguments_len(args);
+                        consumed += optional_len + required_arguments_len(&args[optional_len..], *count);
+                    } else if name == "begin" || name == "end" {
+                        let environment_len = required_arguments_len(args, 1);
+                        let environment = args[..environment_len].trim().trim_start_matches('{').trim_end_matches('}');
+                        consumed += environment_len;
+                        let remaining = &args[environment_len..];
+                        let end_marker = format!("\\end{{{environment}}}");
+                        let is_display = DISPLAY_MATH_ENVIRONMENTS.contains(&environment);
+                        if name == "end" {
+                            // Nothing to skip after the end of an environment
+                        } else if is_display || environment == "math" || SKIPPED_ENVIRONMENTS.contains(&environment) {
+                            consumed += remaining.find(&end_marker).map_or(remaining.len(), |e| e + end_marker.len());
+                            if is_display {
+                                section.display_math += 1;
+                            } else if environment == "math" {
+                                section.inline_math += 1;
+                            }
+                        } else if let Some((_, count)) = ENVIRONMENT_ARGUMENTS.iter().find(|(env, _)| *env == environment) {
+                            let optional_len = optional_arguments_len(remaining);
+                            consumed += optional_len + required_arguments_len(&remaining[optional_len..], *count);
+                        }
+                        emitted = Some('\n');
+                    } else {
+                        // Notes and list items start new words even when written directly after the previous one
+                        if matches!(name, "footnote" | "marginpar" | "item") {
+                            emitted = Some(' ');
+                        }
+                        // Other commands are dropped while their brace groups are counted as text, like texcount does for unknown macros,
+                        // and a single space after the command name only terminates the name
+                        consumed += optional_arguments_len(args);
+                        if rest[consumed..].starts_with(' ') {
+                            consumed += 1;
+                        }
+                    }
+                    (emitted, consumed)
+                }
+            },
+            _ => (Some(c), c.len_utf8()),
+        };
+        if let Some(ch) = emitted {
+            document.text.push(ch);
+            buffers[target as usize].push(ch);
+        }
+        pos += consumed;
+    }
+    close_section(&mut section, &mut buffers, &mut document);
+    document
+}
+
+/// Create a table of the word and formula counts of each section, similar to the per-section breakdown of texcount.
+pub fn generate_section_table(document: &LatexDocument, style: TableStyle) -> Table<String, String> {
+    let headers = vec!["section", "text", "headers", "captions", "inline", "display"].into_iter().map(String::from).collect();
+    let data = document.sections.iter()
+        .map(|s| {
+            let fmt_section = if s.command.is_empty() { "(top)".to_string() } else { format!("{}: {}", s.command, s.title) };
+            vec![fmt_section, s.text_words.to_string(), s.header_words.to_string(), s.caption_words.to_string(), s.inline_math.to_string(), s.display_math.to_string()]
+        })
+        .collect();
+    Table::new_with_style(headers, data, style, "<>>>>>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r"\documentclass{article}
+\usepackage{amsmath}
+\title{Ignored preamble}
+\begin{document}
+Intro words here. % a comment with words
+\section{First Part}
+Text with $x+y$ inline and \cite{key} a citation~reference.
+\begin{figure}
+\includegraphics[width=5cm]{plot.png}
+\caption[short]{A small plot}
+\end{figure}
+\subsection*{Second}
+Display \[ a = b \] and
+\begin{equation} E = mc^2 \end{equation}
+\begin{verbatim}
+not counted words
+\end{verbatim}
+Done\footnote{Note text}.
+\end{document}
+";
+
+    #[test]
+    fn counts_words_and_formulas_per_section() {
+        let document = parse(SOURCE);
+        let sections: Vec<_> = document.sections.iter()
+            .map(|s| (s.command.as_str(), s.title.as_str(), s.text_words, s.header_words, s.caption_words, s.inline_math, s.display_math))
+            .collect();
+        assert_eq!(sections, [
+            ("", "", 3, 0, 0, 0, 0),
+            ("section", "First Part", 7, 2, 3, 1, 0),
+            ("subsection", "Second", 5, 1, 0, 0, 2),
+        ]);
+        let totals = document.totals();
+        assert_eq!((totals.text_words, totals.header_words, totals.caption_words, totals.inline_math, totals.display_math), (15, 3, 3, 1, 2));
+    }
+
+    #[test]
+    fn text_keeps_words_and_drops_markup() {
+        let text = parse(SOURCE).text;
+        let words: Vec<&str> = text.split_whitespace().collect();
+        assert_eq!(words[..5], ["Intro", "words", "here.", "First", "Part"]);
+        assert!(!words.iter().any(|w| ["comment", "counted", "plot.png", "key", "preamble"].iter().any(|skipped| w.contains(skipped))), "{words:?}");
+        assert!(words.contains(&"reference.") && words.contains(&"Note"));
+    }
+
+    #[test]
+    fn sources_without_a_body_are_counted_whole() {
+        let document = parse(r"Plain \emph{emphasized} text with 50\% and \_under");
+        assert_eq!(document.sections.len(), 1);
+        assert_eq!(document.sections[0].text_words, 7);
+        assert!(parse("").sections.is_empty());
+    }
+}
@@ -1,5 +1,34 @@
+pub mod approx;
+pub mod archive;
+pub mod chars;
+pub mod code;
+pub mod compare;
+pub mod compression;
+pub mod document;
+pub mod encoding;
+pub mod entities;
+pub mod histogram;
+pub mod hll;
+pub mod latex;
+pub mod limits;
+pub mod lint;
+pub mod mail;
+pub mod markdown;
+pub mod markup;
+pub mod scan;
+pub mod spell;
+pub mod structured;
+pub mod subtitle;
+pub mod tfidf;
+pub mod zipf;
+
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt::Display;
 
+use rustc_hash::FxBuildHasher;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
 /// Returns the number of digits in the provided value using a more performant log based approach.
 pub fn _count_digits_log(n: usize) -> usize {
     if n == 0 {
@@ -9,6 +38,74 @@ pub fn _count_digits_log(n: usize) -> usize {
     ((n as f64).log(10.0).floor() as usize) + 1
 }
 
+/// Word frequency map keyed by normalized tokens, borrowing from the source text wherever a token needed no normalization.
+pub type WordMap<'a> = HashMap<Cow<'a, str>, usize, FxBuildHasher>;
+
+/// Returns true if a character is kept in a token when not including all characters.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '\''
+}
+
+/// Returns true if normalizing the token would change it, i.e. it has ASCII uppercase characters to lowercase or characters to filter out.
+pub fn needs_normalization(token: &str, is_cased: bool, is_include_all: bool) -> bool {
+    (!is_cased && token.bytes().any(|b| b.is_ascii_uppercase())) || (!is_include_all && !token.chars().all(is_word_char))
+}
+
+/// Normalizes a token into the provided buffer, clearing it first so the same allocation can be reused across tokens.
+pub fn normalize_token_into(token: &str, is_cased: bool, is_include_all: bool, buffer: &mut String) {
+    buffer.clear();
+    let chars = token.chars().filter(|c| is_include_all || is_word_char(*c));
+    if is_cased { buffer.extend(chars) } else { buffer.extend(chars.map(|c| c.to_ascii_lowercase())) }
+}
+
+/// Normalizes a whitespace separated token for frequency mapping, lowercasing it unless `is_cased` and keeping only alphanumeric characters and apostrophes unless `is_include_all`.
+pub fn normalize_token(token: &str, is_cased: bool, is_include_all: bool) -> String {
+    let mut buffer = String::with_capacity(token.len());
+    normalize_token_into(token, is_cased, is_include_all, &mut buffer);
+    buffer
+}
+
+/// Counts the occurrences of each normalized whitespace separated token in the text, skipping tokens left empty by normalization.
+/// Tokens already in normalized form are borrowed from `text` and the rest are normalized into a reused buffer. Per-token allocation is avoided,
+/// but the first occurrence of each distinct word that needed normalization, like a capitalized word or one followed by punctuation, is still copied
+/// into an owned key, so the number of allocations grows with the number of such distinct words.
+pub fn count_words(text: &str, is_cased: bool, is_include_all: bool) -> WordMap<'_> {
+    let mut word_map: WordMap = WordMap::default();
+    let mut buffer = String::new();
+    for token in text.split_whitespace() {
+        if !needs_normalization(token, is_cased, is_include_all) {
+            *word_map.entry(Cow::Borrowed(token)).or_insert(0) += 1;
+            continue;
+        }
+        normalize_token_into(token, is_cased, is_include_all, &mut buffer);
+        if buffer.is_empty() {
+            continue;
+        }
+        match word_map.get_mut(buffer.as_str()) {
+            Some(count) => *count += 1,
+            None => { word_map.insert(Cow::Owned(buffer.clone()), 1); },
+        }
+    }
+    word_map
+}
+
+/// Merges the counts of several word maps into a single map.
+pub fn merge_word_maps<'a>(word_maps: &[WordMap<'a>]) -> WordMap<'a> {
+    let mut merged: WordMap = WordMap::default();
+    for (word, count) in word_maps.iter().flatten() {
+        match merged.get_mut(word.as_ref()) {
+            Some(total) => *total += count,
+            None => { merged.insert(word.clone(), *count); },
+        }
+    }
+    merged
+}
+
+/// Counts the number of lines in the text that are not empty.
+pub fn count_lines(text: &str) -> usize {
+    text.lines().filter(|l| !l.is_empty()).count()
+}
+
 /// Pad a given input (either number or string) to fit within a specified width.
 pub fn pad_input<T: Display>(input: T, max_length: usize, align_right: bool) -> String {
     let input = input.to_string();
@@ -26,22 +123,22 @@ pub fn generate_frequency_table(word_map: &std::collections::HashMap<String, usi
     if top_n < 1 {
         return String::from("")
     }
-    const WID_LABEL: &'static str = "#";
-    const WORD_LABEL: &'static str = "word";
-    const FREQ_LABEL: &'static str = "ct";
-    const VERT_CHAR: &'static str = "│";
-    const TOP_LH_CORNER: &'static str = "┌";
-    const TOP_VERT_SEP: &'static str = "┬";
-    const TOP_RH_CORNER: &'static str = "┐";
-    const MID_LH_BORDER: &'static str = "├";
-    const MID_VERT_SEP: &'static str = "┼";
-    const MID_RH_BORDER: &'static str = "┤";
-    const BOTTOM_LH_CORNER: &'static str = "└";
-    const BOTTOM_VERT_SEP: &'static str = "┴";
-    const BOTTOM_RH_CORNER: &'static str = "┘";
-    const HORIZ_CHAR: &'static str = "─";
-    const NEWLINE_CHAR: &'static str = "\n";
-    const PAD_CHAR: &'static str = " ";
+    const WID_LABEL: &str = "#";
+    const WORD_LABEL: &str = "word";
+    const FREQ_LABEL: &str = "ct";
+    const VERT_CHAR: &str = "│";
+    const TOP_LH_CORNER: &str = "┌";
+    const TOP_VERT_SEP: &str = "┬";
+    const TOP_RH_CORNER: &str = "┐";
+    const MID_LH_BORDER: &str = "├";
+    const MID_VERT_SEP: &str = "┼";
+    const MID_RH_BORDER: &str = "┤";
+    const BOTTOM_LH_CORNER: &str = "└";
+    const BOTTOM_VERT_SEP: &str = "┴";
+    const BOTTOM_RH_CORNER: &str = "┘";
+    const HORIZ_CHAR: &str = "─";
+    const NEWLINE_CHAR: &str = "\n";
+    const PAD_CHAR: &str = " ";
     const PAD_WIDTH: usize = 1;
 
     let mut table_repr = String::new();
@@ -121,20 +218,21 @@ pub struct Table<T, U> {
     pub headers: Vec<T>,
     pub data: Vec<Vec<U>>,
     pub style: TableStyle,
-    pub alignment: Option<String>
+    pub alignment: Option<String>,
+    pub derived: Vec<DerivedColumn> // Optional columns computed from existing numeric columns at render time
 }
 
 /* DataFrame methods go here as they dont require that T and U implement Display or String related methods */
 impl<T, U> Table<T, U> {
     /// Create a new `Table` using the default styling for now
     pub fn new(headers: Vec<T>, data: Vec<Vec<U>>) -> Self {
-        Table { headers, data, style: TableStyle::Normal, alignment: None }
+        Table { headers, data, style: TableStyle::Normal, alignment: None, derived: Vec::new() }
     }
     /// Create a new `Table` with a specified formatting style and column alignment pattern.
     pub fn new_with_style(headers: Vec<T>, data: Vec<Vec<U>>, style: TableStyle, alignment: impl Into<String>) -> Self {
         let alignment: String = alignment.into();
         let alignment = if !alignment.is_empty() { Some (alignment) } else { None };
-        Table { headers, data, style, alignment }
+        Table { headers, data, style, alignment, derived: Vec::new() }
     }
     /// Get shape from data dimensions as a tuple of `(rows, columns)`
     pub fn get_shape(&self) -> (usize, usize) {
@@ -152,6 +250,14 @@ impl<T, U> Table<T, U> {
         let alignment = if !alignment.is_empty() { Some (alignment) } else { None };        
         self.alignment = alignment;
     }    
+    /// Append a proportional bar column drawn from the numeric values in column `source`, scaled so the largest value spans `width` characters.
+    pub fn add_bar_column(&mut self, source: usize, width: usize) {
+        self.derived.push(DerivedColumn::Bar { source, width });
+    }
+    /// Append a column showing the numeric values in column `source` as a percentage of `total`.
+    pub fn add_percent_column(&mut self, source: usize, total: usize) {
+        self.derived.push(DerivedColumn::Percent { source, total });
+    }
     /// Create a tabular data grid of specified dimensions.
     pub fn generate_grid(n_rows: usize, n_cols: usize) -> Table<String, String> {
         let headers = (0..n_cols).map(|c| c.to_string()).collect::<Vec<String>>();
@@ -172,7 +278,7 @@ impl<T, U> Table<T, U> where T: Display, U: Display {
     pub fn to_stringy(self) -> Table<String, String> {
         let headers: Vec<String> = self.headers.into_iter().map(|h| h.to_string()).collect();
         let data: Vec<Vec<String>> = self.data.into_iter().map(|row| row.into_iter().map(|item| item.to_string()).collect()).collect();
-        Table { headers, data, style: self.style, alignment: self.alignment }
+        Table { headers, data, style: self.style, alignment: self.alignment, derived: self.derived }
     }
     /// Converts the header field into a 1D array of Strings to prep for rendering.
     pub fn get_headers_to_string(&self) -> Vec<String> {
@@ -193,25 +299,33 @@ impl<T, U> Display for Table<T, U> where T: Display, U: Display {
         let fmt = self.style.get_style_format();
 
         // Convert headers from T to String:
-        let headers = self.get_headers_to_string();
+        let mut headers = self.get_headers_to_string();
 
         // Convert data from U to String:
-        let data = self.get_data_to_string();
+        let mut data = self.get_data_to_string();
         
         // Get shape of data
         let (_n_rows, n_cols) = self.get_shape();
         
         // Get alignment or use default left-aligned columns if None or invalid input
-        let alignment = self.alignment.as_ref().map_or_else(|| vec![false; n_cols], |s| {
+        let mut alignment = self.alignment.as_ref().map_or_else(|| vec![false; n_cols], |s| {
             if s.chars().count() == n_cols { s.chars().map(|c| c == '>').collect() } else { vec![false; n_cols] }
         });
 
+        // Append any derived columns now that the source values are available as strings
+        for derived in self.derived.iter() {
+            let cells = derived.render(&data);
+            data.iter_mut().zip(cells).for_each(|(row, cell)| row.push(cell));
+            headers.push(derived.header().to_string());
+            alignment.push(derived.is_right_aligned());
+        }
+
         // Initialize the max column widths array using the header widths as a starting point
-        let mut max_widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+        let mut max_widths: Vec<usize> = headers.iter().map(|h| h.width()).collect();
         
-        for cid in 0..n_cols {
+        for (cid, max_width) in max_widths.iter_mut().enumerate() {
             // Calculate max column widths by iterating over the column dimension for each row and keeping largest value
-            max_widths[cid] = max_widths[cid].max(data.iter().filter_map(|row| row.get(cid).map(|item| item.len())).max().unwrap_or(cid));
+            *max_width = (*max_width).max(data.iter().filter_map(|row| row.get(cid).map(|item| item.width())).max().unwrap_or(cid));
         }
         
         // Big things have small beginnings... 
@@ -219,29 +333,29 @@ impl<T, U> Display for Table<T, U> where T: Display, U: Display {
 
         // Create the top horizontal bar
         let fmt_top_bar: Vec<String> = max_widths.iter().map(|w| fmt.top_bar.repeat(*w)).collect();
-        let fmt_top_bar = concat_str!(fmt.top_lh, fmt_top_bar.join(&fmt.top_sep), fmt.top_rh, fmt.crlf);
+        let fmt_top_bar = concat_str!(fmt.top_lh, fmt_top_bar.join(fmt.top_sep), fmt.top_rh, fmt.crlf);
         fmt_table.push_str(&fmt_top_bar);
         
         // Create the header row
         let headers_padded: Vec<String> = headers.iter().enumerate().map(|(hid, header)| pad_or_truncate_input(header, max_widths[hid], alignment[hid])).collect();
-        let fmt_headers = concat_str!(fmt.row_lh, headers_padded.join(&fmt.row_sep), fmt.row_rh, fmt.crlf);
+        let fmt_headers = concat_str!(fmt.row_lh, headers_padded.join(fmt.row_sep), fmt.row_rh, fmt.crlf);
         fmt_table.push_str(&fmt_headers);
 
         // Create the middle horizontal separator bar
         let fmt_mid_bar: Vec<String> = max_widths.iter().map(|w| fmt.mid_bar.repeat(*w)).collect();
-        let fmt_mid_bar = concat_str!(fmt.mid_lh, fmt_mid_bar.join(&fmt.mid_sep), fmt.mid_rh, fmt.crlf);
+        let fmt_mid_bar = concat_str!(fmt.mid_lh, fmt_mid_bar.join(fmt.mid_sep), fmt.mid_rh, fmt.crlf);
         fmt_table.push_str(&fmt_mid_bar);
 
         // Create the body of the table from the rows of data
         for row in data.into_iter() {
             let row_padded: Vec<String> = row.iter().enumerate().map(|(cid, cell)| pad_or_truncate_input(cell, max_widths[cid], alignment[cid])).collect();
-            let fmt_row = concat_str!(fmt.row_lh, row_padded.join(&fmt.row_sep), fmt.row_rh, fmt.crlf);
+            let fmt_row = concat_str!(fmt.row_lh, row_padded.join(fmt.row_sep), fmt.row_rh, fmt.crlf);
             fmt_table.push_str(&fmt_row);
         }
 
         // Create the bottom horizontal bar
         let fmt_end_bar: Vec<String> = max_widths.iter().map(|w| fmt.end_bar.repeat(*w)).collect();
-        let fmt_end_bar = concat_str!(fmt.end_lh, fmt_end_bar.join(&fmt.end_sep), fmt.end_rh, fmt.crlf);
+        let fmt_end_bar = concat_str!(fmt.end_lh, fmt_end_bar.join(fmt.end_sep), fmt.end_rh, fmt.crlf);
         fmt_table.push_str(&fmt_end_bar);        
 
         // Create the caption
@@ -255,6 +369,47 @@ impl<T, U> Display for Table<T, U> where T: Display, U: Display {
     }
 }
 
+#[derive(Debug, Clone)]
+/// Defines a column computed from the values of another numeric column when a `Table` is rendered.
+pub enum DerivedColumn {
+    /// Proportional bar relative to the largest value in the `source` column, spanning at most `width` characters.
+    Bar { source: usize, width: usize },
+    /// Percentage of `total` represented by each value in the `source` column.
+    Percent { source: usize, total: usize },
+}
+
+impl DerivedColumn {
+    /// Returns the header label for the derived column.
+    pub fn header(&self) -> &'static str {
+        match self {
+            Self::Bar { .. } => "",
+            Self::Percent { .. } => "%",
+        }
+    }
+    /// Returns true if the derived column should be right-aligned.
+    pub fn is_right_aligned(&self) -> bool {
+        matches!(self, Self::Percent { .. })
+    }
+    /// Render the derived cells for each row of `data`, treating non-numeric source values as zero.
+    pub fn render(&self, data: &[Vec<String>]) -> Vec<String> {
+        let source_values = |source: usize| -> Vec<usize> {
+            data.iter().map(|row| row.get(source).and_then(|v| v.trim().parse::<usize>().ok()).unwrap_or(0)).collect()
+        };
+        match self {
+            Self::Bar { source, width } => {
+                let values = source_values(*source);
+                let max_value = values.iter().copied().max().unwrap_or(0);
+                values.into_iter().map(|v| render_bar(v, max_value, *width)).collect()
+            },
+            Self::Percent { source, total } => {
+                source_values(*source).into_iter()
+                    .map(|v| if *total > 0 { format!("{:.1}%", v as f64 * 100.0 / *total as f64) } else { "-".to_string() })
+                    .collect()
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 /// Defines styling for `Table` struct.
 pub enum TableStyle {
@@ -286,6 +441,7 @@ pub struct TableStyleFormat {
 
 impl TableStyleFormat {
     /// Create a new format based on positions of formatting characters.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(top_lh: &'static str,top_bar: &'static str,top_sep: &'static str,top_rh: &'static str,mid_lh: &'static str,mid_bar: &'static str,mid_sep: &'static str,mid_rh: &'static str,row_lh: &'static str,row_sep: &'static str,row_rh: &'static str,end_lh: &'static str,end_bar: &'static str,end_sep: &'static str,end_rh: &'static str) -> Self {
         TableStyleFormat {top_lh,top_bar,top_sep,top_rh,mid_lh,mid_bar,mid_sep,mid_rh,row_lh,row_sep,row_rh,end_lh,end_bar,end_sep,end_rh,crlf:"\n"} 
     }
@@ -319,11 +475,18 @@ impl TableStyle {
 /// Pad a given input (either number or string) to fit within a specified width.
 pub fn pad_or_truncate_input<T: Display>(input: T, max_length: usize, align_right: bool) -> String {
     let input = input.to_string();
-    let input_length = input.len();
-    if input_length > max_length {
-        concat_str!(input[..max_length.saturating_sub(2)], "..")
+    let input_width = input.width(); // Measure display columns so wide glyphs like CJK and emoji line up with the borders
+    if input_width > max_length {
+        // Keep whole characters within the width left for the ellipsis, padding if a wide character did not fit
+        let mut width = 0;
+        let truncated: String = input.chars().take_while(|c| {
+            width += c.width().unwrap_or(0);
+            width <= max_length.saturating_sub(2)
+        }).collect();
+        let padding = " ".repeat(max_length.saturating_sub(truncated.width() + 2));
+        concat_str!(truncated, "..", padding)
     } else {
-        let padding = " ".repeat(max_length.saturating_sub(input.len()));
+        let padding = " ".repeat(max_length.saturating_sub(input_width));
         
         if align_right {
             concat_str!(padding, input)
@@ -331,4 +494,49 @@ pub fn pad_or_truncate_input<T: Display>(input: T, max_length: usize, align_righ
             concat_str!(input, padding)
         }
     }
-}
\ No newline at end of file
+}
+
+/// Block characters used to draw partial bar cells in eighths, from empty to a full block.
+const BAR_EIGHTHS: [&str; 9] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉", "█"];
+
+/// Render a horizontal bar proportional to `value / max_value`, spanning at most `max_width` characters with eighth-block precision.
+pub fn render_bar(value: usize, max_value: usize, max_width: usize) -> String {
+    if max_value == 0 || max_width == 0 {
+        return String::new()
+    }
+    // Work in eighths of a character cell so partial blocks can be drawn at the end of the bar
+    let eighths = ((value.min(max_value) as f64 / max_value as f64) * (max_width * 8) as f64).round() as usize;
+    let eighths = if value > 0 { eighths.max(1) } else { eighths }; // Always show something for non-zero values
+    concat_str!(BAR_EIGHTHS[8].repeat(eighths / 8), BAR_EIGHTHS[eighths % 8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pad_measures_display_width() {
+        assert_eq!(pad_or_truncate_input("日本", 6, false), "日本  ");
+        assert_eq!(pad_or_truncate_input("🎉", 4, true), "  🎉");
+        assert_eq!(pad_or_truncate_input("word", 6, true), "  word");
+    }
+
+    #[test]
+    fn truncate_keeps_whole_wide_characters() {
+        assert_eq!(pad_or_truncate_input("日本語テキスト", 7, false), "日本.. ");
+        assert_eq!(pad_or_truncate_input("abcdefgh", 6, false), "abcd..");
+    }
+
+    #[test]
+    fn table_rows_line_up_with_wide_cells() {
+        let headers = vec!["#".to_string(), "emoji".to_string(), "ct".to_string()];
+        let data = vec![
+            vec!["1".to_string(), "🎉".to_string(), "12".to_string()],
+            vec!["2".to_string(), "日本語".to_string(), "3".to_string()],
+            vec!["3".to_string(), "x".to_string(), "100".to_string()],
+        ];
+        let table = Table::new_with_style(headers, data, TableStyle::Polars, "><>").to_string();
+        let widths: Vec<usize> = table.lines().map(UnicodeWidthStr::width).collect();
+        assert!(widths.windows(2).all(|pair| pair[0] == pair[1]), "{table}");
+    }
+}
@@ -0,0 +1,132 @@
+use crate::scan;
+
+/// Characters that may follow the end of a sentence within the same token, like closing quotes and brackets.
+const CLOSING_PUNCTUATION: [char; 7] = ['"', '\'', ')', ']', '”', '’', '*'];
+
+#[derive(Debug, Clone, Default)]
+/// Limits each input is checked against, all optional.
+pub struct Limits {
+    pub max_words: Option<usize>,
+    pub min_words: Option<usize>,
+    pub max_lines: Option<usize>, // Maximum number of non-empty lines
+    pub max_sentence_words: Option<usize>,
+    pub forbidden: Vec<String>, // Words that may not appear at all, normalized like counted words
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A broken limit, either of the whole input or at a 1-based line.
+pub struct Violation {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Violation {
+    /// Formats the violation like a compiler diagnostic, e.g. `post.md:12: sentence of 41 words exceeds the maximum of 30`.
+    pub fn fmt(&self, name: &str) -> String {
+        match self.line {
+            Some(line) => format!("{}:{}: {}", name, line, self.message),
+            None => format!("{}: {}", name, self.message),
+        }
+    }
+}
+
+/// Returns true if the token ends a sentence with a full stop, question mark or exclamation mark, ignoring closing quotes and brackets.
+fn is_sentence_end(token: &str) -> bool {
+    token.trim_end_matches(CLOSING_PUNCTUATION).ends_with(['.', '!', '?', '…'])
+}
+
+/// Checks the text against the limits, returning the violations of the whole input followed by those of each line in order.
+/// Words and lines are counted the same way as the totals, and a blank line ends a sentence as well so headings and list items
+/// without a full stop are not joined to the next paragraph.
+pub fn check(text: &str, limits: &Limits, is_cased: bool, is_include_all: bool) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let counts = scan::scan_counts(text, is_include_all);
+    if let Some(max_words) = limits.max_words.filter(|max| counts.words > *max) {
+        violations.push(Violation { line: None, message: format!("{} words exceeds the maximum of {}", counts.words, max_words) });
+    }
+    if let Some(min_words) = limits.min_words.filter(|min| counts.words < *min) {
+        violations.push(Violation { line: None, message: format!("{} words is below the minimum of {}", counts.words, min_words) });
+    }
+    if let Some(max_lines) = limits.max_lines.filter(|max| counts.lines > *max) {
+        violations.push(Violation { line: None, message: format!("{} lines exceeds the maximum of {}", counts.lines, max_lines) });
+    }
+    if limits.max_sentence_words.is_none() && limits.forbidden.is_empty() {
+        return violations
+    }
+
+    let mut word = String::new(); // Reused normalization buffer to avoid allocating per token
+    let (mut sentence_start, mut sentence_words) = (1_usize, 0_usize);
+    let check_sentence = |start: usize, words: usize, violations: &mut Vec<Violation>| {
+        if let Some(max) = limits.max_sentence_words.filter(|max| words > *max) {
+            violations.push(Violation { line: Some(start), message: format!("sentence of {} words exceeds the maximum of {}", words, max) });
+        }
+    };
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            check_sentence(sentence_start, sentence_words, &mut violations);
+            sentence_words = 0;
+            continue;
+        }
+        for token in line.split_whitespace() {
+            crate::normalize_token_into(token, is_cased, is_include_all, &mut word);
+            if !word.is_empty() {
+                if sentence_words == 0 {
+                    sentence_start = i + 1;
+                }
+                sentence_words += 1;
+                if limits.forbidden.contains(&word) {
+                    violations.push(Violation { line: Some(i + 1), message: format!("forbidden word {:?}", word) });
+                }
+            }
+            if is_sentence_end(token) {
+                check_sentence(sentence_start, sentence_words, &mut violations);
+                sentence_words = 0;
+            }
+        }
+    }
+    check_sentence(sentence_start, sentence_words, &mut violations);
+    // Sentences are checked once they end, which may be after forbidden words on later lines
+    violations.sort_by_key(|violation| violation.line);
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POST: &str = "# Title\n\nA short first sentence. Then a second one that runs on for a while!\n\nThe end\n";
+
+    #[test]
+    fn no_violations_within_limits() {
+        // An empty result is what lets `run_limits` exit with status 0
+        let limits = Limits { max_words: Some(20), min_words: Some(5), max_lines: Some(3), max_sentence_words: Some(10), forbidden: vec!["very".to_string()] };
+        assert_eq!(check(POST, &limits, false, false), []);
+        assert_eq!(check(POST, &Limits::default(), false, false), []);
+    }
+
+    #[test]
+    fn reports_totals_before_lines() {
+        let limits = Limits { max_words: Some(10), min_words: Some(30), max_lines: Some(2), ..Limits::default() };
+        let messages: Vec<_> = check(POST, &limits, false, false).into_iter().map(|v| (v.line, v.message)).collect();
+        assert_eq!(messages, [
+            (None, "17 words exceeds the maximum of 10".to_string()),
+            (None, "17 words is below the minimum of 30".to_string()),
+            (None, "3 lines exceeds the maximum of 2".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn sentences_and_forbidden_words_by_line() {
+        let text = "One two three\nfour five. Six\n\nseven Eight nine ten eleven!\n";
+        let limits = Limits { max_sentence_words: Some(4), forbidden: vec!["eight".to_string()], ..Limits::default() };
+        let violations = check(text, &limits, false, false);
+        assert_eq!(violations, [
+            Violation { line: Some(1), message: "sentence of 5 words exceeds the maximum of 4".to_string() },
+            Violation { line: Some(4), message: "forbidden word \"eight\"".to_string() },
+            Violation { line: Some(4), message: "sentence of 5 words exceeds the maximum of 4".to_string() },
+        ]);
+        assert_eq!(violations[0].fmt("post.md"), "post.md:1: sentence of 5 words exceeds the maximum of 4");
+        // Forbidden words are normalized like counted words, so case only matters with `is_cased`
+        assert_eq!(check(text, &limits, true, false).len(), 2);
+    }
+}
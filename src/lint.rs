@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use unicode_properties::{GeneralCategory, GeneralCategoryGroup, UnicodeGeneralCategory};
+use unicode_script::{Script, UnicodeScript};
+use unicode_security::skeleton;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Defines the kinds of suspicious characters reported by the character linter.
+pub enum LintKind {
+    Invisible, // Zero width and other format characters that render as nothing
+    Bidi, // Bidirectional embedding, override, isolate and mark characters that reorder the displayed text
+    Control, // Control characters other than tabs and line breaks, along with the Unicode line and paragraph separators
+    Space, // Spaces other than U+0020, like no-break and em spaces
+    Confusable // Letters of another script mixed into a word that look like letters of the word's own script
+}
+
+impl LintKind {
+    /// Every kind of suspicious character.
+    pub const ALL: [LintKind; 5] = [Self::Invisible, Self::Bidi, Self::Control, Self::Space, Self::Confusable];
+
+    /// Parses a plural lint label like `confusables`.
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.label() == label)
+    }
+    /// Returns the plural label of the lint kind, used for option values.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Invisible => "invisible",
+            Self::Bidi => "bidi",
+            Self::Control => "controls",
+            Self::Space => "spaces",
+            Self::Confusable => "confusables",
+        }
+    }
+    /// Returns the singular label of the lint kind, shown after each finding.
+    fn singular(&self) -> &'static str {
+        match self {
+            Self::Invisible => "invisible",
+            Self::Bidi => "bidi",
+            Self::Control => "control",
+            Self::Space => "space",
+            Self::Confusable => "confusable",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A suspicious character at a 1-based line and column, counted in characters.
+pub struct Finding {
+    pub line: usize,
+    pub column: usize,
+    pub c: char,
+    pub kind: LintKind,
+    pub description: String,
+}
+
+impl Finding {
+    /// Formats the finding like a compiler diagnostic, e.g. `notes.txt:3:14: U+200B zero width space (invisible)`.
+    pub fn fmt(&self, name: &str) -> String {
+        format!("{}:{}:{}: U+{:04X} {} ({})", name, self.line, self.column, self.c as u32, self.description, self.kind.singular())
+    }
+}
+
+/// Returns the name of a well known invisible, bidirectional, control or space character.
+fn char_name(c: char) -> Option<&'static str> {
+    let name = match c {
+        '\u{0000}' => "null",
+        '\u{0007}' => "bell",
+        '\u{0008}' => "backspace",
+        '\u{000B}' => "vertical tab",
+        '\u{000C}' => "form feed",
+        '\r' => "carriage return",
+        '\u{001B}' => "escape",
+        '\u{007F}' => "delete",
+        '\u{0085}' => "next line",
+        '\u{00A0}' => "no-break space",
+        '\u{00AD}' => "soft hyphen",
+        '\u{034F}' => "combining grapheme joiner",
+        '\u{061C}' => "arabic letter mark",
+        '\u{115F}' | '\u{1160}' | '\u{3164}' | '\u{FFA0}' => "hangul filler",
+        '\u{1680}' => "ogham space mark",
+        '\u{180E}' => "mongolian vowel separator",
+        '\u{2000}' => "en quad",
+        '\u{2001}' => "em quad",
+        '\u{2002}' => "en space",
+        '\u{2003}' => "em space",
+        '\u{2004}' => "three-per-em space",
+        '\u{2005}' => "four-per-em space",
+        '\u{2006}' => "six-per-em space",
+        '\u{2007}' => "figure space",
+        '\u{2008}' => "punctuation space",
+        '\u{2009}' => "thin space",
+        '\u{200A}' => "hair space",
+        '\u{200B}' => "zero width space",
+        '\u{200C}' => "zero width non-joiner",
+        '\u{200D}' => "zero width joiner",
+        '\u{200E}' => "left-to-right mark",
+        '\u{200F}' => "right-to-left mark",
+        '\u{2028}' => "line separator",
+        '\u{2029}' => "paragraph separator",
+        '\u{202A}' => "left-to-right embedding",
+        '\u{202B}' => "right-to-left embedding",
+        '\u{202C}' => "pop directional formatting",
+        '\u{202D}' => "left-to-right override",
+        '\u{202E}' => "right-to-left override",
+        '\u{202F}' => "narrow no-break space",
+        '\u{205F}' => "medium mathematical space",
+        '\u{2060}' => "word joiner",
+        '\u{2061}'..='\u{2064}' => "invisible math operator",
+        '\u{2066}' => "left-to-right isolate",
+        '\u{2067}' => "right-to-left isolate",
+        '\u{2068}' => "first strong isolate",
+        '\u{2069}' => "pop directional isolate",
+        '\u{3000}' => "ideographic space",
+        '\u{FEFF}' => "zero width no-break space",
+        '\u{E0000}'..='\u{E007F}' => "tag character",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Classifies a single character as invisible, bidirectional, control or space, ignoring tabs and line feeds.
+fn classify(c: char) -> Option<LintKind> {
+    match c {
+        '\t' | '\n' | ' ' => None,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => Some(LintKind::Bidi),
+        '\u{00AD}' | '\u{034F}' | '\u{115F}' | '\u{1160}' | '\u{180E}' | '\u{3164}' | '\u{FFA0}' => Some(LintKind::Invisible),
+        '\u{2028}' | '\u{2029}' => Some(LintKind::Control),
+        _ => match c.general_category() {
+            GeneralCategory::Control => Some(LintKind::Control),
+            GeneralCategory::Format => Some(LintKind::Invisible),
+            GeneralCategory::SpaceSeparator => Some(LintKind::Space),
+            _ => None,
+        },
+    }
+}
+
+/// Returns true if the script writes words with joiners between its letters, as Persian does with the zero width non-joiner.
+fn is_joining_script(script: Script) -> bool {
+    !matches!(script, Script::Latin | Script::Greek | Script::Cyrillic | Script::Common | Script::Inherited | Script::Unknown)
+}
+
+/// Returns true if an invisible character is a legitimate part of the surrounding text, like the zero width joiners of an emoji
+/// sequence, joiners between letters of a script that uses them or the tag characters of a subdivision flag.
+fn is_expected_invisible(c: char, prev: Option<char>, next: Option<char>) -> bool {
+    let is_emoji = |c: Option<char>| c.is_some_and(|c| c.general_category_group() == GeneralCategoryGroup::Symbol || c == '\u{FE0F}');
+    match c {
+        '\u{200D}' if is_emoji(prev) && is_emoji(next) => true,
+        '\u{200C}' | '\u{200D}' => match (prev, next) {
+            (Some(prev), Some(next)) => prev.script() == next.script() && is_joining_script(prev.script()),
+            _ => false,
+        },
+        '\u{E0020}'..='\u{E007F}' => is_emoji(prev) || prev.is_some_and(|p| ('\u{E0020}'..='\u{E007F}').contains(&p)),
+        _ => false,
+    }
+}
+
+/// Returns the script most of the letters of a mixed-script word are written in, preferring Latin on ties,
+/// or `None` if the word is written in a single script.
+fn dominant_script(word: &[char]) -> Option<Script> {
+    let mut counts: HashMap<Script, usize> = HashMap::new();
+    for script in word.iter().map(|c| c.script()).filter(|s| !matches!(s, Script::Common | Script::Inherited)) {
+        *counts.entry(script).or_insert(0) += 1;
+    }
+    if counts.len() < 2 {
+        return None
+    }
+    counts.into_iter().max_by_key(|(script, count)| (*count, *script == Script::Latin)).map(|(script, _)| script)
+}
+
+/// Appends a finding for each letter of the word whose script differs from the rest of the word and whose confusable skeleton
+/// is made of letters of that script, like the Cyrillic `а` in `pаypal`.
+fn lint_word(word: &[char], line: usize, start_column: usize, findings: &mut Vec<Finding>) {
+    let Some(dominant) = dominant_script(word) else {
+        return
+    };
+    let text: String = word.iter().collect();
+    for (i, c) in word.iter().enumerate() {
+        let script = c.script();
+        if matches!(script, Script::Common | Script::Inherited) || script == dominant {
+            continue;
+        }
+        let prototype: String = skeleton(&c.to_string()).collect();
+        let is_lookalike = prototype != c.to_string()
+            && prototype.chars().all(|p| matches!(p.script(), Script::Common | Script::Inherited) || p.script() == dominant);
+        if is_lookalike {
+            findings.push(Finding {
+                line,
+                column: start_column + i,
+                c: *c,
+                kind: LintKind::Confusable,
+                description: format!("{} letter looking like {:?} in {:?}", script.full_name(), prototype, text),
+            });
+        }
+    }
+}
+
+/// Finds the invisible, bidirectional, control, space and confusable characters of the kinds requested, in order of position.
+/// A byte order mark at the very start and a carriage return ending a line are not reported.
+pub fn lint(text: &str, kinds: &[LintKind]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (line_index, line) in text.split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let chars: Vec<char> = line.chars().collect();
+        let line_number = line_index + 1;
+        let mut word_start = None;
+        for (i, &c) in chars.iter().enumerate() {
+            // Words are runs of letters, digits and marks checked for confusables once they end
+            let is_word_char = c.is_alphanumeric() || c.general_category_group() == GeneralCategoryGroup::Mark;
+            match (is_word_char, word_start) {
+                (true, None) => word_start = Some(i),
+                (false, Some(start)) => {
+                    if kinds.contains(&LintKind::Confusable) {
+                        lint_word(&chars[start..i], line_number, start + 1, &mut findings);
+                    }
+                    word_start = None;
+                },
+                _ => (),
+            }
+
+            let Some(kind) = classify(c).filter(|kind| kinds.contains(kind)) else {
+                continue
+            };
+            let (prev, next) = (i.checked_sub(1).map(|p| chars[p]), chars.get(i + 1).copied());
+            if (c == '\u{FEFF}' && line_index == 0 && i == 0) || (kind == LintKind::Invisible && is_expected_invisible(c, prev, next)) {
+                continue;
+            }
+            let description = char_name(c).map_or_else(|| match kind {
+                LintKind::Control => "control character".to_string(),
+                LintKind::Space => "space".to_string(),
+                _ => "format character".to_string(),
+            }, str::to_string);
+            findings.push(Finding { line: line_number, column: i + 1, c, kind, description });
+        }
+        if let Some(start) = word_start.filter(|_| kinds.contains(&LintKind::Confusable)) {
+            lint_word(&chars[start..], line_number, start + 1, &mut findings);
+        }
+    }
+    findings.sort_by_key(|finding| (finding.line, finding.column));
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the line, column and kind of each finding of every kind in the text.
+    fn positions(text: &str) -> Vec<(usize, usize, LintKind)> {
+        lint(text, &LintKind::ALL).into_iter().map(|finding| (finding.line, finding.column, finding.kind)).collect()
+    }
+
+    #[test]
+    fn classifies_suspicious_characters() {
+        assert_eq!(classify('\u{200B}'), Some(LintKind::Invisible));
+        assert_eq!(classify('\u{00AD}'), Some(LintKind::Invisible));
+        assert_eq!(classify('\u{202E}'), Some(LintKind::Bidi));
+        assert_eq!(classify('\u{0007}'), Some(LintKind::Control));
+        assert_eq!(classify('\u{2028}'), Some(LintKind::Control));
+        assert_eq!(classify('\u{00A0}'), Some(LintKind::Space));
+        assert_eq!(['a', ' ', '\t', '\n', 'é', '😀'].map(classify), [None; 6]);
+    }
+
+    #[test]
+    fn reports_line_and_column_in_characters() {
+        let findings = lint("ok\n  é\u{200B}y and\u{00A0}z\n\u{202E}", &LintKind::ALL);
+        let positions: Vec<_> = findings.iter().map(|f| (f.line, f.column, f.kind)).collect();
+        assert_eq!(positions, [(2, 4, LintKind::Invisible), (2, 10, LintKind::Space), (3, 1, LintKind::Bidi)]);
+        assert_eq!(findings[0].fmt("notes.txt"), "notes.txt:2:4: U+200B zero width space (invisible)");
+        // Only the requested kinds are reported
+        assert_eq!(lint("a\u{00A0}b\u{200B}", &[LintKind::Space]).len(), 1);
+    }
+
+    #[test]
+    fn leading_bom_and_crlf_are_not_reported() {
+        assert_eq!(positions("\u{FEFF}first\r\nsecond\r\n"), []);
+        assert_eq!(positions("first\n\u{FEFF}second"), [(2, 1, LintKind::Invisible)]);
+        assert_eq!(positions("carriage\rreturn"), [(1, 9, LintKind::Control)]);
+    }
+
+    #[test]
+    fn joiners_and_tags_in_context_are_expected() {
+        // Family emoji joined with zero width joiners, a Persian word with a zero width non-joiner and the flag of England
+        assert_eq!(positions("👨\u{200D}👩\u{200D}👧 می\u{200C}خواهم 🏴\u{E0067}\u{E0062}\u{E0065}\u{E006E}\u{E0067}\u{E007F}"), []);
+        assert!(is_expected_invisible('\u{200D}', Some('❤'), Some('\u{FE0F}')));
+        // The same characters between Latin letters or after plain text hide something
+        assert_eq!(positions("ab\u{200C}cd x\u{200D}y a\u{E0041}"), [(1, 3, LintKind::Invisible), (1, 8, LintKind::Invisible), (1, 12, LintKind::Invisible)]);
+    }
+
+    #[test]
+    fn detects_confusable_letters_of_another_script() {
+        let findings = lint("pay at\npаypal now", &[LintKind::Confusable]);
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].line, findings[0].column, findings[0].c, findings[0].kind), (2, 2, 'а', LintKind::Confusable));
+        assert!(findings[0].description.starts_with("Cyrillic letter looking like \"a\""), "{}", findings[0].description);
+        // Words written entirely in one script are fine, whichever script it is
+        assert_eq!(positions("пароль paypal Ελλάδα"), []);
+    }
+
+    #[test]
+    fn labels_round_trip() {
+        assert!(LintKind::ALL.iter().all(|kind| LintKind::from_label(kind.label()) == Some(*kind)));
+        assert_eq!(LintKind::from_label("control"), None);
+    }
+}
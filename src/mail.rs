@@ -0,0 +1,372 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::encoding::{self, Encoding};
+use crate::markup::{self, MarkupKind};
+
+/// Markers after which the rest of a reply is the quoted original message, as written by Outlook and similar clients.
+const ORIGINAL_MESSAGE_MARKERS: [&str; 2] = ["-----Original Message-----", "-----Ursprüngliche Nachricht-----"];
+
+/// Unfolded headers of a message or MIME part as lowercase names and raw values, in order.
+type Headers = Vec<(String, String)>;
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Selects which non-body parts of an email are kept when extracting its text, all of them are skipped by default.
+pub struct MailOptions {
+    pub is_include_headers: bool, // Keep the decoded header lines, like `Subject: ...`, ahead of the body
+    pub is_include_quotes: bool, // Keep quoted reply blocks, their attribution lines and forwarded originals
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines how messages are aggregated into counted inputs.
+pub enum MailGroup {
+    Sender, // One input per sender address
+    Thread // One input per conversation, following `References` and `In-Reply-To` and falling back to the subject
+}
+
+impl MailGroup {
+    /// Parses a grouping label.
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "sender" | "from" => Some(Self::Sender),
+            "thread" => Some(Self::Thread),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A single decoded email message.
+pub struct Message {
+    pub sender: String, // Lowercase address of the `From` header
+    pub subject: String, // Decoded subject with reply and forward prefixes like `Re:` removed
+    pub thread: String, // Message id of the first message of the conversation, or the subject for messages without ids
+    pub text: String, // Text of the body parts, with headers and quotes kept according to the options
+}
+
+/// Returns true if the bytes look like an mbox archive rather than a single message, i.e. they start with a `From ` separator line.
+pub fn is_mbox(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"From ")
+}
+
+/// Splits an mbox archive into its messages at `From ` lines that start the file or follow a blank line, dropping the separator
+/// lines and unescaping body lines that were escaped as `>From `.
+fn split_mbox(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut messages: Vec<Vec<u8>> = Vec::new();
+    let mut is_after_blank = true;
+    for line in bytes.split_inclusive(|b| *b == b'\n') {
+        if is_after_blank && line.starts_with(b"From ") {
+            messages.push(Vec::new());
+        } else if let Some(message) = messages.last_mut() {
+            // Escaped `From ` lines have one extra `>` in mboxrd archives
+            let quotes = line.iter().take_while(|b| **b == b'>').count();
+            let line = if quotes > 0 && line[quotes..].starts_with(b"From ") { &line[1..] } else { line };
+            message.extend_from_slice(line);
+        }
+        is_after_blank = line.iter().all(u8::is_ascii_whitespace);
+    }
+    messages
+}
+
+/// Splits a message or MIME part into its unfolded headers and its body.
+fn split_headers(bytes: &[u8]) -> (Headers, &[u8]) {
+    let mut headers: Headers = Vec::new();
+    let mut offset = 0;
+    for line in bytes.split_inclusive(|b| *b == b'\n') {
+        offset += line.len();
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            return (headers, &bytes[offset..])
+        }
+        match headers.last_mut() {
+            // Folded headers continue on lines starting with whitespace
+            Some((_, value)) if line.starts_with([' ', '\t']) => {
+                value.push(' ');
+                value.push_str(line.trim());
+            },
+            _ => if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+            },
+        }
+    }
+    (headers, &[])
+}
+
+/// Returns the value of the first header with the lowercase `name`.
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+}
+
+/// Parses a header value like `text/plain; charset="utf-8"` into its lowercase value and its parameters with lowercase names.
+fn parse_parameters(value: &str) -> (String, Vec<(String, String)>) {
+    let mut parts = value.split(';');
+    let main = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+    let parameters = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().trim_matches('"').to_string()))
+        .collect();
+    (main, parameters)
+}
+
+/// Returns the value of a hexadecimal digit.
+fn hex_value(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+/// Decodes quoted-printable content, joining soft line breaks and leaving invalid escapes as they are.
+/// In encoded words `is_header` also decodes `_` as a space.
+fn decode_quoted_printable(bytes: &[u8], is_header: bool) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'=' if bytes[i + 1..].starts_with(b"\r\n") => i += 3,
+            b'=' if bytes[i + 1..].starts_with(b"\n") => i += 2,
+            b'=' if i + 2 < bytes.len() && hex_value(bytes[i + 1]).is_some() && hex_value(bytes[i + 2]).is_some() => {
+                decoded.push(hex_value(bytes[i + 1]).unwrap_or_default() << 4 | hex_value(bytes[i + 2]).unwrap_or_default());
+                i += 3;
+            },
+            b'_' if is_header => { decoded.push(b' '); i += 1; },
+            b => { decoded.push(b); i += 1; },
+        }
+    }
+    decoded
+}
+
+/// Decodes base64 content, ignoring line breaks and any other characters outside the alphabet.
+fn decode_base64(bytes: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(bytes.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0_u32, 0_u32);
+    for b in bytes.iter() {
+        let value = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            _ => continue,
+        };
+        buffer = buffer << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    decoded
+}
+
+/// Decodes text in the named charset, falling back to UTF-8 for unknown charsets and US-ASCII, which UTF-8 extends.
+fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+    encoding::decode(bytes, charset.and_then(Encoding::from_label).or(Some(Encoding::Utf8))).text.into_owned()
+}
+
+/// Decodes RFC 2047 encoded words like `=?utf-8?Q?caf=C3=A9?=` in a header value, dropping the whitespace between adjacent encoded words.
+fn decode_header(value: &str) -> String {
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    let mut is_after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let word = &rest[start + 2..];
+        let parts: Vec<&str> = word.splitn(3, '?').collect();
+        let end = parts.get(2).and_then(|text| text.find("?="));
+        let (Some(end), &[charset, encoding, text]) = (end, parts.as_slice()) else {
+            break;
+        };
+        let between = &rest[..start];
+        if !(is_after_word && between.trim().is_empty()) {
+            decoded.push_str(between);
+        }
+        let bytes = match encoding.to_ascii_uppercase().as_str() {
+            "B" => decode_base64(&text.as_bytes()[..end]),
+            _ => decode_quoted_printable(&text.as_bytes()[..end], true),
+        };
+        // Charsets may carry a language suffix like `utf-8*en`
+        decoded.push_str(&decode_charset(&bytes, charset.split('*').next()));
+        rest = &text[end + 2..];
+        is_after_word = true;
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Returns the lowercase address of a header like `Jane Doe <Jane@Example.com>`.
+fn parse_address(value: &str) -> String {
+    let address = match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &value[start + 1..end],
+        _ => value,
+    };
+    address.trim().trim_matches('"').to_ascii_lowercase()
+}
+
+/// Removes reply and forward prefixes like `Re:`, `Fwd:` or `AW:` from a subject, repeatedly.
+fn normalize_subject(subject: &str) -> String {
+    let mut subject = subject.trim();
+    while let Some((prefix, rest)) = subject.split_once(':') {
+        let prefix = prefix.trim().trim_end_matches(|c: char| c == ']' || c == '[' || c.is_ascii_digit()).to_ascii_lowercase();
+        if !["re", "fw", "fwd", "aw", "wg", "sv", "vs", "tr", "antw"].contains(&prefix.as_str()) {
+            break;
+        }
+        subject = rest.trim();
+    }
+    subject.to_string()
+}
+
+/// Removes quoted reply blocks from a message body: lines starting with `>`, the `On ... wrote:` attribution line introducing them
+/// and everything after an `-----Original Message-----` marker.
+fn strip_quotes(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut stripped = String::with_capacity(text.len());
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if ORIGINAL_MESSAGE_MARKERS.iter().any(|m| trimmed.starts_with(m)) {
+            break;
+        }
+        let is_attribution = trimmed.ends_with("wrote:")
+            && lines[i + 1..].iter().find(|l| !l.trim().is_empty()).is_some_and(|l| l.trim_start().starts_with('>'));
+        if !trimmed.starts_with('>') && !is_attribution {
+            stripped.push_str(line);
+            stripped.push('\n');
+        }
+    }
+    stripped
+}
+
+/// Extracts the text of a MIME part, recursing into multipart and attached messages. Of alternative parts the plain text one is
+/// preferred, HTML parts are reduced to their text, and attachments and other content types are skipped.
+fn part_text(headers: &[(String, String)], body: &[u8], options: MailOptions) -> String {
+    let (content_type, parameters) = parse_parameters(header(headers, "content-type").unwrap_or("text/plain"));
+    let parameter = |name: &str| parameters.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+    let (disposition, _) = parse_parameters(header(headers, "content-disposition").unwrap_or_default());
+    if disposition == "attachment" {
+        return String::new()
+    }
+    if content_type.starts_with("multipart/") {
+        let Some(boundary) = parameter("boundary") else {
+            return String::new()
+        };
+        let parts: Vec<(Headers, &[u8])> = split_multipart(body, boundary).into_iter().map(split_headers).collect();
+        if content_type == "multipart/alternative" {
+            let is_plain = |(headers, _): &&(Headers, &[u8])| parse_parameters(header(headers, "content-type").unwrap_or("text/plain")).0 == "text/plain";
+            return parts.iter().find(is_plain).or(parts.last()).map_or_else(String::new, |(headers, body)| part_text(headers, body, options))
+        }
+        return parts.iter().map(|(headers, body)| part_text(headers, body, options)).filter(|t| !t.is_empty()).collect::<Vec<_>>().join("\n")
+    }
+    if content_type == "message/rfc822" {
+        return parse_message(body, options).text
+    }
+    if content_type != "text/plain" && content_type != "text/html" {
+        return String::new()
+    }
+    let bytes: Cow<[u8]> = match header(headers, "content-transfer-encoding").map(|e| e.trim().to_ascii_lowercase()).as_deref() {
+        Some("base64") => Cow::Owned(decode_base64(body)),
+        Some("quoted-printable") => Cow::Owned(decode_quoted_printable(body, false)),
+        _ => Cow::Borrowed(body),
+    };
+    let text = decode_charset(&bytes, parameter("charset"));
+    let text = if content_type == "text/html" { markup::extract_text(&text, MarkupKind::Html, None) } else { text };
+    if options.is_include_quotes { text } else { strip_quotes(&text) }
+}
+
+/// Splits a multipart body into its parts at the `--boundary` delimiter lines, dropping the preamble and epilogue.
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{boundary}");
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut offset = 0;
+    for line in body.split_inclusive(|b| *b == b'\n') {
+        let trimmed = line.trim_ascii_end();
+        if trimmed.starts_with(delimiter.as_bytes()) {
+            if let Some(start) = start {
+                // The line break before the delimiter belongs to the delimiter
+                let end = if body[..offset].ends_with(b"\r\n") { offset - 2 } else { offset.saturating_sub(1) };
+                parts.push(&body[start..end.max(start)]);
+            }
+            if trimmed[delimiter.len()..].starts_with(b"--") {
+                return parts
+            }
+            start = Some(offset + line.len());
+        }
+        offset += line.len();
+    }
+    if let Some(start) = start {
+        parts.push(&body[start..]);
+    }
+    parts
+}
+
+/// Parses a single message, decoding its MIME structure, transfer encodings, charsets and encoded header words.
+pub fn parse_message(bytes: &[u8], options: MailOptions) -> Message {
+    let (headers, body) = split_headers(bytes);
+    let decoded_header = |name: &str| header(&headers, name).map(decode_header).unwrap_or_default();
+    let subject = decoded_header("subject");
+    // The first id in `References` is the start of the conversation
+    let thread = header(&headers, "references").and_then(|r| r.split_whitespace().next())
+        .or_else(|| header(&headers, "in-reply-to").and_then(|r| r.split_whitespace().next()))
+        .or_else(|| header(&headers, "message-id"))
+        .map_or_else(|| normalize_subject(&subject).to_lowercase(), |id| id.trim().to_string());
+    let mut text = if options.is_include_headers {
+        headers.iter().map(|(name, value)| format!("{}: {}\n", name, decode_header(value))).collect()
+    } else {
+        String::new()
+    };
+    text.push_str(&part_text(&headers, body, options));
+    Message { sender: parse_address(&decoded_header("from")), subject: normalize_subject(&subject), thread, text }
+}
+
+/// Parses every message of an mbox archive, or the single message of an EML file.
+pub fn parse_messages(bytes: &[u8], options: MailOptions) -> Vec<Message> {
+    if is_mbox(bytes) {
+        split_mbox(bytes).iter().map(|message| parse_message(message, options)).collect()
+    } else {
+        vec![parse_message(bytes, options)]
+    }
+}
+
+/// Names the messages for display, numbering them along with their subject, or aggregates their text by sender or thread.
+/// Groups are named by sender address or by the subject of the first message of the thread, in order of first appearance.
+pub fn group_messages(messages: Vec<Message>, group: Option<MailGroup>) -> Vec<(String, String)> {
+    let Some(group) = group else {
+        return messages.into_iter().enumerate().map(|(i, m)| (format!("{}: {}", i + 1, m.subject), m.text)).collect()
+    };
+    let mut groups: Vec<(String, String)> = Vec::new(); // Name and text of each group
+    let mut indexes: HashMap<String, usize> = HashMap::new(); // Position of each group by sender address or thread id
+    for message in messages {
+        let key = if group == MailGroup::Sender { message.sender.clone() } else { message.thread.clone() };
+        match indexes.get(&key) {
+            Some(&i) => {
+                groups[i].1.push('\n');
+                groups[i].1.push_str(&message.text);
+            },
+            None => {
+                indexes.insert(key, groups.len());
+                let name = if group == MailGroup::Sender { message.sender } else { message.subject };
+                groups.push((name, message.text));
+            },
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_printable_soft_line_breaks() {
+        assert_eq!(decode_quoted_printable(b"long para=\r\ngraph and=\nmore", false), b"long paragraph andmore");
+        assert_eq!(decode_quoted_printable(b"caf=C3=A9 =3D ok=\r\n", false), "café = ok".as_bytes());
+        // A trailing or invalid escape is kept as is instead of panicking
+        assert_eq!(decode_quoted_printable(b"bad =ZZ end=", false), b"bad =ZZ end=");
+        assert_eq!(decode_quoted_printable(b"a_b=5Fc", true), b"a b_c");
+    }
+
+    #[test]
+    fn quoted_printable_body_words_are_joined() {
+        let message = b"Subject: test\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: quoted-printable\r\n\r\nun=\r\nbroken na=C3=AFve\r\n";
+        let message = parse_message(message, MailOptions::default());
+        assert_eq!(message.text.split_whitespace().collect::<Vec<_>>(), ["unbroken", "naïve"]);
+    }
+}
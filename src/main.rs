@@ -1,52 +1,636 @@
-use std::fs::{read_to_string, metadata};
-use std::io;
-use std::collections::HashMap;
-use wclib::{self};
+use std::borrow::Cow;
+use std::fs::{metadata, File};
+use std::io::{self, BufRead, Read};
+use std::path::{Path, PathBuf};
+use memmap2::Mmap;
+use wclib::{self, concat_str, WordMap};
+use wclib::approx::{self, SpaceSaving};
+use wclib::archive;
+use wclib::chars::{self, CharCounts};
+use wclib::code::{self, Language};
+use wclib::compare;
+use wclib::compression::{self, Compression};
+use wclib::document;
+use wclib::encoding::{self, BinaryMode, Encoding};
+use wclib::entities::{self, EntityCounts};
+use wclib::hll::HyperLogLog;
+use wclib::latex;
+use wclib::limits::{self, Limits};
+use wclib::lint;
+use wclib::mail;
+use wclib::markdown;
+use wclib::markup;
+use wclib::scan;
+use wclib::spell::{self, Dictionary, Misspellings};
+use wclib::structured::{self, DataField, DataKind};
+use wclib::subtitle;
+use wclib::histogram::{Histogram, HistogramKind};
+use wclib::tfidf::{self, TfIdfLayout};
+use wclib::zipf::{self as zipf, ZipfFit};
 
 mod args;
 
+use args::WCArgs;
+
 /// A list of 184 common stopwords to ignore when doing word frequency mapping.
 const STOPWORDS: [&str; 184] = ["a","about","above","actually","after","again","against","all","almost","also","although","always","am","an","and","any","are","as","at","be","became","become","because","been","before","being","below","between","both","but","by","can","could","did","do","does","doing","down","during","each","either","else","few","for","from","further","had","has","have","having","he","he'd","he'll","hence","he's","her","here","here's","hers","herself","him","himself","his","how","how's","I","I'd","I'll","I'm","I've","if","in","into","is","it","it's","its","itself","just","let's","may","maybe","me","might","mine","more","most","must","my","myself","neither","nor","not","of","oh","on","once","only","ok","or","other","ought","our","ours","ourselves","out","over","own","same","she","she'd","she'll","she's","should","so","some","such","than","that","that's","the","their","theirs","them","themselves","then","there","there's","these","they","they'd","they'll","they're","they've","this","those","through","to","too","under","until","up","very","was","we","we'd","we'll","we're","we've","were","what","what's","when","whenever","when's","where","whereas","wherever","where's","whether","which","while","who","whoever","who's","whose","whom","why","why's","will","with","within","would","yes","yet","you","you'd","you'll","you're","you've","your","yours","yourself","yourselves"];
 
-fn main() -> io::Result<()> {
-    let args = args::parse_args();
-
+/// Checks that the provided path exists and is not a directory, reporting failures to stderr.
+fn check_input(filename: &Path) -> io::Result<()> {
     // Check if the provided path is a directory
-    if let Ok(meta) = metadata(&args.filename) {
+    if let Ok(meta) = metadata(filename) {
         if meta.is_dir() {
-            eprintln!("Provided filename {:?} appears to be a directory and not a file.", args.filename);
+            eprintln!("Provided filename {:?} appears to be a directory and not a file.", filename);
             return Err(io::Error::new(io::ErrorKind::InvalidInput, "Input is a directory, not a file."));
         }
     } else {
         // Handle the case where metadata retrieval failed
-        eprintln!("Error retrieving metadata for {:?}: file may not exist.", args.filename);
+        eprintln!("Error retrieving metadata for {:?}: file may not exist.", filename);
         return Err(io::Error::new(io::ErrorKind::NotFound, "File not found."));
     }
+    Ok(())
+}
+
+/// Decompresses the input in memory if it is compressed, reporting decompression failures to stderr.
+/// Input only detected by its magic bytes that fails to decompress is passed through as is, since plain text may start with the same bytes.
+fn decompress_input<'a>(path: &Path, name: &str, bytes: &'a [u8]) -> io::Result<Cow<'a, [u8]>> {
+    match compression::detect(path, bytes) {
+        Some(compression) => match compression::decompress(bytes, compression) {
+            Ok(decompressed) => Ok(Cow::Owned(decompressed)),
+            Err(_) if Compression::from_extension(path).is_none() => Ok(Cow::Borrowed(bytes)),
+            Err(e) => {
+                eprintln!("Error decompressing {} file {:?}: {}", compression.label(), name, e);
+                Err(e)
+            },
+        },
+        None => Ok(Cow::Borrowed(bytes)),
+    }
+}
+
+/// Returns true if the input looks binary and binary files are not treated as text, warning about the skipped file unless skipped silently.
+/// Only the leading bytes are inspected, so `bytes` may be the head of a streamed input.
+fn is_skipped_binary(name: &str, bytes: &[u8], args: &WCArgs) -> bool {
+    if args.binary_mode != BinaryMode::Text && encoding::is_binary_input(bytes, args.encoding) {
+        if args.binary_mode == BinaryMode::Warn {
+            eprintln!("Skipping binary file {:?}, use `--binary-files text` to count it anyway.", name);
+        }
+        return true;
+    }
+    false
+}
+
+/// Warns about invalid bytes replaced while decoding the input.
+fn warn_replaced(name: &str, replaced: usize, encoding: Encoding) {
+    if replaced > 0 {
+        eprintln!("Warning: replaced {} invalid bytes in {:?} while decoding as {}.", replaced, name, encoding.label());
+    }
+}
+
+/// Decodes the input according to the input options, returning `None` if it looks binary and binary files are not treated as text.
+fn decode_input<'a>(name: &str, bytes: &'a [u8], args: &WCArgs) -> Option<Cow<'a, str>> {
+    if is_skipped_binary(name, bytes, args) {
+        return None;
+    }
+    let decoded = encoding::decode(bytes, args.encoding);
+    warn_replaced(name, decoded.replaced, decoded.encoding);
+    Some(decoded.text)
+}
+
+/// A single counted input, i.e. a file, an archive member or a document section, along with any report specific to its format.
+struct Input<'a> {
+    name: String,
+    record: Option<String>, // Record of the selected data field the text is a value of, summarized with the rest of the file but exported on its own
+    text: &'a str,
+    report: Option<String>,
+}
+
+/// Returns the name of an input qualified with its data field record, if any, as listed in the counts export and position reports.
+fn record_name(name: &str, record: Option<&str>) -> String {
+    record.map_or_else(|| name.to_string(), |record| format!("{name}/{record}"))
+}
+
+/// Returns the path of an input as it was before compression, so the format of files like `main.rs.gz` is detected from their original extension.
+fn uncompressed_path(name: &str) -> PathBuf {
+    let path = Path::new(name);
+    if Compression::from_extension(path).is_some() { path.with_extension("") } else { path.to_path_buf() }
+}
+
+/// Extracts the countable text from markup or source code according to the input options, passing plain text through unchanged.
+/// Formats with their own statistics also return a report to display alongside the counts.
+/// Returns `None` if the input is source code whose language cannot be detected from its name or shebang line.
+fn extract_text<'a>(name: &str, text: Cow<'a, str>, args: &WCArgs) -> Option<(Cow<'a, str>, Option<String>)> {
+    if let Some(options) = args.markdown {
+        Some((Cow::Owned(markdown::extract_prose(&text, options)), None))
+    } else if let Some(kind) = args.markup {
+        Some((Cow::Owned(markup::extract_text(&text, kind, args.selector.as_ref())), None))
+    } else if let Some(part) = args.code_part {
+        let Some(language) = args.code_language.or_else(|| Language::from_extension(&uncompressed_path(name))).or_else(|| Language::from_shebang(&text)) else {
+            eprintln!("Skipping {:?}, its language could not be detected, use `--code <LANG>` to set it.", name);
+            return None;
+        };
+        Some((Cow::Owned(code::extract(&text, language, part)), None))
+    } else if args.is_latex {
+        let document = latex::parse(&text);
+        let totals = document.totals();
+        let report = format!("{name}:\n{}{} words in text, {} in headers, {} in captions, {} inline and {} displayed formulas\n",
+            latex::generate_section_table(&document, wclib::TableStyle::Polars), totals.text_words, totals.header_words, totals.caption_words, totals.inline_math, totals.display_math);
+        Some((Cow::Owned(document.text), Some(report)))
+    } else if let Some(max_wpm) = args.subtitle_max_wpm {
+        let cues = subtitle::parse(&text, subtitle::SubtitleKind::detect(&uncompressed_path(name), &text));
+        let report = format!("{name}:\n{}{}", subtitle::generate_cue_table(&cues, max_wpm, wclib::TableStyle::Polars), subtitle::summarize(&cues, max_wpm));
+        Some((Cow::Owned(cues.into_iter().map(|cue| cue.text + "\n").collect()), Some(report)))
+    } else {
+        Some((text, None))
+    }
+}
+
+/// Passes the text of a single input that is not an archive to `f`, extracting DOCX, ODT and EPUB documents from their XML
+/// with one call per chapter for EPUB, and email with one call per message or group of messages. Other input is decoded as text,
+/// with one call per value when a data field is selected.
+fn read_text(name: String, bytes: &[u8], args: &WCArgs, f: &mut impl FnMut(Input)) -> io::Result<()> {
+    if let Some(kind) = document::DocumentKind::detect(bytes) {
+        let sections = document::extract(bytes, kind).inspect_err(|e| eprintln!("Error reading {} document {:?}: {}", kind.label(), name, e))?;
+        for (section, text) in sections {
+            f(Input { name: section.map_or_else(|| name.clone(), |section| format!("{name}/{section}")), record: None, text: &text, report: None });
+        }
+        return Ok(());
+    }
+    if let Some(options) = args.mail {
+        // Messages are decoded from the raw bytes since each MIME part names its own charset
+        for (message, text) in mail::group_messages(mail::parse_messages(bytes, options), args.mail_group) {
+            f(Input { name: format!("{name}/{message}"), record: None, text: &text, report: None });
+        }
+        return Ok(());
+    }
+    let Some(text) = decode_input(&name, bytes, args) else {
+        return Ok(());
+    };
+    if let Some(field) = args.data_field.as_ref() {
+        // Fields are counted one value at a time, defaulting to JSON or CSV when the extension does not name the format
+        let kind = DataKind::from_extension(&uncompressed_path(&name))
+            .unwrap_or(if matches!(field, DataField::JsonPath(_)) { DataKind::Json } else { DataKind::Csv });
+        return structured::for_each_record(&text, kind, field, |record, value| {
+            if let Some((text, report)) = extract_text(&record_name(&name, Some(&record)), Cow::Owned(value), args) {
+                f(Input { name: name.clone(), record: Some(record), text: &text, report });
+            }
+        }).inspect_err(|e| eprintln!("Error reading fields of {:?}: {}", name, e));
+    }
+    if let Some((text, report)) = extract_text(&name, text, args) {
+        f(Input { name, record: None, text: &text, report });
+    }
+    Ok(())
+}
+
+/// Memory maps the provided file, reporting directories, missing files and read failures to stderr. Empty files are not mapped.
+fn map_input(filename: &Path) -> io::Result<Option<Mmap>> {
+    check_input(filename)?;
+    scan::map_file(filename).inspect_err(|e| eprintln!("Error reading file {:?}: {}", filename, e))
+}
+
+/// Memory maps and decompresses the provided file, passing the decoded text to `f` along with its display name.
+/// Archives are read member by member without extracting them to disk, calling `f` once per member passing the include and exclude filters
+/// with the member path appended to the archive name. Inputs that look binary are skipped unless binary files are treated as text.
+fn for_each_input(filename: &Path, args: &WCArgs, mut f: impl FnMut(Input)) -> io::Result<()> {
+    let mmap = map_input(filename)?;
+    read_mapped(filename, mmap.as_deref().unwrap_or_default(), args, &mut f)
+}
+
+/// Decompresses the mapped bytes of the provided file in memory and passes the text of the file or of each of its archive members to `f`.
+fn read_mapped(filename: &Path, bytes: &[u8], args: &WCArgs, f: &mut impl FnMut(Input)) -> io::Result<()> {
+    let name = fmt_filename(filename);
+    // Compressed inputs keep their original name in the output
+    let bytes = decompress_input(filename, &name, bytes)?;
+
+    // Documents are zip archives too, but their members are only meaningful together
+    if let Some(kind) = archive::ArchiveKind::from_magic(&bytes).filter(|_| document::DocumentKind::detect(&bytes).is_none()) {
+        return archive::for_each_member(&bytes, kind, &args.member_filter, |member, contents| {
+            let member_name = format!("{name}/{member}");
+            let contents = decompress_input(Path::new(member), &member_name, contents)?;
+            read_text(member_name, &contents, args, f)
+        }).inspect_err(|e| eprintln!("Error reading {} archive {:?}: {}", kind.label(), filename, e));
+    }
+    read_text(name, &bytes, args, f)
+}
 
-    // Attempt to read the file and handle errors gracefully
-    let raw_contents = match read_to_string(&args.filename) {
-        Ok(contents) => contents,
+/// Size of the blocks streamed inputs are decompressed and decoded in, extended to the end of the line so no line or character is split.
+const CHUNK_LEN: usize = 256 * 1024;
+
+/// Decodes a decompressed stream in chunks of whole lines, keeping the first error to report once the stream has been counted.
+struct TextChunks<R: BufRead> {
+    reader: R,
+    encoding: Option<Encoding>, // Explicit encoding, or the encoding detected from the first chunk
+    buffer: Vec<u8>,
+    replaced: usize, // Number of invalid bytes replaced while decoding
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> Iterator for TextChunks<R> {
+    type Item = Cow<'static, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buffer.clear();
+        let result = self.reader.by_ref().take(CHUNK_LEN as u64).read_to_end(&mut self.buffer)
+            .and_then(|_| if self.buffer.last().is_some_and(|b| *b != b'\n') { self.reader.read_until(b'\n', &mut self.buffer) } else { Ok(0) });
+        if let Err(e) = result {
+            self.error = Some(e);
+            return None
+        }
+        if self.buffer.is_empty() {
+            return None
+        }
+        let decoded = encoding::decode(&self.buffer, self.encoding);
+        self.encoding = Some(decoded.encoding);
+        self.replaced += decoded.replaced;
+        Some(Cow::Owned(decoded.text.into_owned()))
+    }
+}
+
+/// A counted input read as a sequence of text chunks that each end at a line break.
+struct ChunkedInput<'a, 't> {
+    name: String,
+    record: Option<String>,
+    chunks: &'a mut dyn Iterator<Item = Cow<'t, str>>,
+    report: Option<String>,
+}
+
+/// Returns true if inputs are counted as plain text without extracting a format, a data field or email messages from them.
+fn is_plain_text(args: &WCArgs) -> bool {
+    args.markdown.is_none() && args.markup.is_none() && args.code_part.is_none() && !args.is_latex && args.subtitle_max_wpm.is_none()
+        && args.mail.is_none() && args.data_field.is_none()
+}
+
+/// Like `for_each_input`, but compressed plain text is decompressed and decoded one chunk at a time as `f` reads it rather than in memory,
+/// which keeps the memory use of the streaming counters bounded for large compressed logs. Other input is passed to `f` as a single chunk.
+fn for_each_input_chunks(filename: &Path, args: &WCArgs, mut f: impl FnMut(ChunkedInput)) -> io::Result<()> {
+    let mmap = map_input(filename)?;
+    let bytes = mmap.as_deref().unwrap_or_default();
+    if let Some(compression) = compression::detect(filename, bytes).filter(|_| is_plain_text(args)) {
+        if stream_decompressed(filename, bytes, compression, args, &mut f)? {
+            return Ok(());
+        }
+    }
+    read_mapped(filename, bytes, args, &mut |input: Input| {
+        f(ChunkedInput { name: input.name, record: input.record, chunks: &mut std::iter::once(Cow::Borrowed(input.text)), report: input.report })
+    })
+}
+
+/// Streams a compressed plain text file through its decoder to `f`, returning false without calling `f` if the decompressed data is an archive,
+/// a document or in a wide encoding, which are read in memory instead, or if it fails to decompress when only its magic bytes suggested compression.
+fn stream_decompressed(filename: &Path, bytes: &[u8], compression: Compression, args: &WCArgs, f: &mut impl FnMut(ChunkedInput)) -> io::Result<bool> {
+    let name = fmt_filename(filename);
+    let mut reader = io::BufReader::with_capacity(CHUNK_LEN, compression::decoder(bytes, compression));
+    // The head is inspected for archives, documents, byte order marks and binary data before anything is counted
+    let mut head = Vec::new();
+    match reader.by_ref().take(encoding::BINARY_SNIFF_LEN as u64).read_to_end(&mut head) {
+        Ok(_) => (),
+        Err(_) if Compression::from_extension(filename).is_none() => return Ok(false),
         Err(e) => {
-            eprintln!("Error reading file {:?}: {}", args.filename, e);
-            return Err(e); // Return the error to the caller
+            eprintln!("Error decompressing {} file {:?}: {}", compression.label(), name, e);
+            return Err(e);
+        },
+    }
+    let is_wide = args.encoding.or_else(|| encoding::detect_bom(&head)).is_some_and(|e| e.is_wide());
+    if is_wide || archive::ArchiveKind::from_magic(&head).is_some() || document::DocumentKind::detect(&head).is_some() {
+        return Ok(false);
+    }
+    if is_skipped_binary(&name, &head, args) {
+        return Ok(true);
+    }
+
+    let mut chunks = TextChunks { reader: io::Cursor::new(head).chain(reader), encoding: args.encoding, buffer: Vec::new(), replaced: 0, error: None };
+    f(ChunkedInput { name: name.clone(), record: None, chunks: &mut chunks, report: None });
+    if let Some(e) = chunks.error {
+        eprintln!("Error decompressing {} file {:?}: {}", compression.label(), name, e);
+        return Err(e);
+    }
+    warn_replaced(&name, chunks.replaced, chunks.encoding.unwrap_or(Encoding::Utf8));
+    Ok(true)
+}
+
+/// Reads and decodes the contents of the provided file or of each of its archive members, reporting directories, missing files and read failures to stderr.
+/// Any reports specific to the input format are appended to `reports`. The values of a data field are joined into one document per file,
+/// with the counts of each record appended to `record_counts` when exporting counts, after removing entities like the documents themselves.
+fn read_input(filename: &Path, args: &WCArgs, reports: &mut String, record_counts: &mut Vec<(String, scan::ScanCounts)>) -> io::Result<Vec<(String, String)>> {
+    let mut inputs: Vec<(String, String)> = Vec::new();
+    for_each_input(filename, args, |input| {
+        reports.push_str(input.report.as_deref().unwrap_or_default());
+        let Some(record) = input.record else {
+            inputs.push((input.name, input.text.to_string()));
+            return;
+        };
+        if args.counts_export.is_some() {
+            let text = args.entity_kinds.as_ref().map_or(Cow::Borrowed(input.text), |kinds| {
+                Cow::Owned(entities::extract(input.text, kinds, args.is_cased, &mut EntityCounts::default()))
+            });
+            record_counts.push((record_name(&input.name, Some(&record)), scan::scan_counts(&text, args.is_include_all)));
+        }
+        // Values are separated by a line break so the last word of one record is not joined to the first word of the next
+        match inputs.last_mut() {
+            Some((name, text)) if *name == input.name => {
+                text.push('\n');
+                text.push_str(input.text);
+            },
+            _ => inputs.push((input.name, input.text.to_string())),
         }
+    })?;
+    Ok(inputs)
+}
+
+/// Returns true if the word should appear in frequency based output, i.e. it is neither a stopword (unless included) nor one of the ignored words.
+fn is_counted(word: &str, args: &WCArgs) -> bool {
+    is_counted_lowercase(word, &word.to_ascii_lowercase(), args)
+}
+
+/// Like `is_counted`, with the lowercase form of the word for the stopwords check provided by the caller so it can reuse a buffer.
+fn is_counted_lowercase(word: &str, lowercase: &str, args: &WCArgs) -> bool {
+    (args.is_include_stopwords || !STOPWORDS.contains(&lowercase)) // Passes stopwords check
+        && args.ignore_words.as_ref().is_none_or(|iwords| !iwords.iter().any(|w| w == word)) // Passes additional ignore words check
+}
+
+/// Streams each memory mapped file line by line into bounded memory estimators so memory does not grow with the number of distinct words.
+fn run_streaming(args: &WCArgs) -> io::Result<()> {
+    let mut summary = args.approx_capacity.map(SpaceSaving::new);
+    let mut hll = args.hll_precision.map(HyperLogLog::new);
+    let (mut word_count, mut line_count) = (0_usize, 0_usize);
+    let mut word = String::new(); // Reused normalization buffer to avoid allocating per token
+    let mut lowercase = String::new(); // Reused buffer for the stopwords check of cased words
+    let mut fmt_reports = String::new();
+    let mut input_counts: Vec<(String, scan::ScanCounts)> = Vec::new(); // Per input totals, only kept when exporting them
+    for filename in args.filenames.iter() {
+        for_each_input_chunks(filename, args, |input| {
+            fmt_reports.push_str(input.report.as_deref().unwrap_or_default());
+            let (input_word_start, input_line_start) = (word_count, line_count);
+            for chunk in input.chunks {
+                for line in chunk.lines() {
+                    if !line.is_empty() {
+                        line_count += 1;
+                    }
+                    for token in line.split_whitespace() {
+                        wclib::normalize_token_into(token, args.is_cased, args.is_include_all, &mut word);
+                        if word.is_empty() {
+                            continue;
+                        }
+                        word_count += 1;
+                        if let Some(hll) = hll.as_mut() {
+                            hll.insert(word.as_str());
+                        }
+                        // Only words that can be displayed compete for counters, which tightens the error bounds
+                        if let Some(summary) = summary.as_mut() {
+                            wclib::normalize_token_into(&word, false, true, &mut lowercase);
+                            if is_counted_lowercase(&word, &lowercase, args) {
+                                summary.insert(&word);
+                            }
+                        }
+                    }
+                }
+            }
+            if args.counts_export.is_some() {
+                input_counts.push((record_name(&input.name, input.record.as_deref()), scan::ScanCounts { words: word_count - input_word_start, lines: line_count - input_line_start }));
+            }
+        })?;
+    }
+    export_counts(args, &input_counts)?;
+
+    let top_n = args.top_words.unwrap_or_default();
+    let fmt_freq_table = match summary {
+        Some(summary) if top_n > 0 => {
+            let table = approx::generate_approx_table(&summary, top_n, wclib::TableStyle::Polars);
+            format!("{table}approximate counts tracking {} words, overestimated by at most {}\n", summary.capacity(), summary.max_error())
+        },
+        _ => "".to_string(),
     };
+    let fmt_unique = hll.map_or_else(String::new, |hll| {
+        format!("~{:.0} unique (±{:.2}%), ", hll.estimate(), hll.standard_error() * 100.0)
+    });
+    println!("{fmt_freq_table}{fmt_reports}{} words, {}{} lines", word_count, fmt_unique, line_count);
+    Ok(())
+}
 
-    // Count the number of lines in the raw content that are not empty
-    let line_count = raw_contents.lines().filter(|l| !l.is_empty()).count();
+/// Counts words and non-empty lines of each file or archive member with the memory mapped byte scanner, printing per input and overall totals.
+/// The values of a data field are summarized per file, and only listed per record in the counts export.
+fn run_totals(args: &WCArgs) -> io::Result<()> {
+    let mut input_counts: Vec<(String, scan::ScanCounts)> = Vec::new();
+    let mut export_rows: Vec<(String, scan::ScanCounts)> = Vec::new(); // Per input or per record totals, only kept when exporting them
+    let mut fmt_reports = String::new();
+    for filename in args.filenames.iter() {
+        for_each_input_chunks(filename, args, |input| {
+            fmt_reports.push_str(input.report.as_deref().unwrap_or_default());
+            // Chunks end at line breaks, so their counts add up to the counts of the whole input
+            let mut counts = scan::ScanCounts::default();
+            input.chunks.for_each(|chunk| counts += scan::scan_counts(&chunk, args.is_include_all));
+            if args.counts_export.is_some() {
+                export_rows.push((record_name(&input.name, input.record.as_deref()), counts));
+            }
+            match input_counts.last_mut() {
+                Some((name, total)) if input.record.is_some() && *name == input.name => *total += counts,
+                _ => input_counts.push((input.name, counts)),
+            }
+        })?;
+    }
+    export_counts(args, &export_rows)?;
+    let fmt_file_summaries = if input_counts.len() > 1 {
+        input_counts.iter().map(|(name, counts)| format!("{}: {} words, {} lines\n", name, counts.words, counts.lines)).collect::<String>()
+    } else {
+        "".to_string()
+    };
+    let word_count: usize = input_counts.iter().map(|(_, counts)| counts.words).sum();
+    let line_count: usize = input_counts.iter().map(|(_, counts)| counts.lines).sum();
+    let fmt_total = if input_counts.len() > 1 { "total: " } else { "" };
+    println!("{fmt_reports}{fmt_file_summaries}{fmt_total}{} words, {} lines", word_count, line_count);
+    Ok(())
+}
 
-    let mut word_map: HashMap<String, usize> = HashMap::new();
+/// Lists the suspicious characters of each file or archive member by line and column, returning true if none were found.
+fn run_lint(args: &WCArgs, kinds: &[lint::LintKind]) -> io::Result<bool> {
+    let (mut finding_count, mut flagged_count, mut input_count) = (0_usize, 0_usize, 0_usize);
+    for filename in args.filenames.iter() {
+        for_each_input(filename, args, |input| {
+            let findings = lint::lint(input.text, kinds);
+            let name = record_name(&input.name, input.record.as_deref());
+            findings.iter().for_each(|finding| println!("{}", finding.fmt(&name)));
+            finding_count += findings.len();
+            flagged_count += usize::from(!findings.is_empty());
+            input_count += 1;
+        })?;
+    }
+    println!("{} suspicious characters in {} of {} inputs", finding_count, flagged_count, input_count);
+    Ok(finding_count == 0)
+}
 
-    // Count the top N word frequencies and display them along with the counts
-    for word in raw_contents.split_whitespace() {
-        let word = if !args.is_cased { word.to_ascii_lowercase() } else { word.into() };
-        let word = if args.is_include_all { word } else { word.chars().filter(|c| c.is_alphanumeric() || *c == '\'').collect() };
-        if word.is_empty() {
-            continue;
+/// Spell checks the words of each file or archive member against the dictionary, listing misspellings with their counts and line numbers.
+/// Ignored words are accepted as well. Returns true if no words were misspelled.
+fn run_spellcheck(args: &WCArgs, dic_path: &Path) -> io::Result<bool> {
+    let mut dictionary = Dictionary::load(dic_path).inspect_err(|e| eprintln!("Error reading dictionary {:?}: {}", dic_path, e))?;
+    if let Some(path) = args.personal_words.as_ref() {
+        dictionary.add_personal_words(path).inspect_err(|e| eprintln!("Error reading personal word list {:?}: {}", path, e))?;
+    }
+    let is_ignored = |word: &str| args.ignore_words.as_ref().is_some_and(|iwords| {
+        iwords.iter().any(|w| if args.is_cased { w == word } else { *w == word.to_lowercase() })
+    });
+    let mut misspellings = Misspellings::default();
+    for filename in args.filenames.iter() {
+        for_each_input(filename, args, |input| misspellings.check_text(&record_name(&input.name, input.record.as_deref()), input.text, &dictionary, is_ignored))?;
+    }
+    let fmt_table = if misspellings.total() > 0 {
+        spell::generate_misspelling_table(&misspellings, wclib::TableStyle::Polars).to_string()
+    } else {
+        "".to_string()
+    };
+    let (flagged_count, input_count) = misspellings.flagged_inputs();
+    println!("{fmt_table}{} misspelled words, {} distinct, in {} of {} inputs", misspellings.total(), misspellings.distinct(), flagged_count, input_count);
+    Ok(misspellings.total() == 0)
+}
+
+/// Collects the files in the directory and its subdirectories in sorted order, skipping hidden entries like `.git` and files whose path
+/// relative to the directory does not pass the include and exclude patterns.
+fn walk_directory(dir: &Path, args: &WCArgs) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let mut entries = std::fs::read_dir(&current)?.map(|entry| entry.map(|e| e.path())).collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        for path in entries.into_iter().rev() {
+            if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+                continue;
+            }
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.strip_prefix(dir).is_ok_and(|relative| args.member_filter.is_match(&fmt_filename(relative))) {
+                files.push(path);
+            }
         }
-        *word_map.entry(word).or_insert_with(|| 0) += 1;
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Checks each file or archive member against the limits, listing every violation, with directories checked file by file.
+/// Returns true if all inputs are within the limits.
+fn run_limits(args: &WCArgs, limits: &Limits) -> io::Result<bool> {
+    let (mut violation_count, mut flagged_count, mut input_count) = (0_usize, 0_usize, 0_usize);
+    let mut filenames = Vec::new();
+    for filename in args.filenames.iter() {
+        if filename.is_dir() {
+            filenames.extend(walk_directory(filename, args).inspect_err(|e| eprintln!("Error reading directory {:?}: {}", filename, e))?);
+        } else {
+            filenames.push(filename.clone());
+        }
+    }
+    for filename in filenames.iter() {
+        for_each_input(filename, args, |input| {
+            let violations = limits::check(input.text, limits, args.is_cased, args.is_include_all);
+            let name = record_name(&input.name, input.record.as_deref());
+            violations.iter().for_each(|violation| println!("{}", violation.fmt(&name)));
+            violation_count += violations.len();
+            flagged_count += usize::from(!violations.is_empty());
+            input_count += 1;
+        })?;
+    }
+    println!("{} violations in {} of {} inputs", violation_count, flagged_count, input_count);
+    Ok(violation_count == 0)
+}
+
+/// Exports the counts of each input as CSV if requested, reporting failures to stderr.
+fn export_counts(args: &WCArgs, counts: &[(String, scan::ScanCounts)]) -> io::Result<()> {
+    let Some(path) = args.counts_export.as_ref() else {
+        return Ok(())
+    };
+    File::create(path)
+        .and_then(|file| scan::export_counts(&mut io::BufWriter::new(file), counts))
+        .inspect_err(|e| eprintln!("Error exporting counts to {:?}: {}", path, e))
+}
+
+/// Formats a filename for display using forward slashes regardless of platform.
+fn fmt_filename(filename: &Path) -> String {
+    filename.to_string_lossy().replace("\\", "/")
+}
+
+fn main() -> io::Result<()> {
+    let args = args::parse_args();
+
+    // Linting, spell checking and limits report positions rather than counts, failing the process for CI when anything is found
+    if let Some(kinds) = args.lint_kinds.as_ref() {
+        if !run_lint(&args, kinds)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(dic_path) = args.spell_dictionary.as_ref() {
+        if !run_spellcheck(&args, dic_path)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(limits) = args.limits.as_ref() {
+        if !run_limits(&args, limits)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Plain totals are scanned directly from memory mapped input
+    if args.is_totals_only() {
+        return run_totals(&args);
+    }
+
+    // Bounded memory modes stream the input and never build the full frequency map
+    if args.approx_capacity.is_some() || args.hll_precision.is_some() {
+        return run_streaming(&args);
     }
 
+    // Read every input up front so a missing or unreadable file fails before any output, archives contribute one document per member
+    let mut documents: Vec<(String, String)> = Vec::new();
+    let mut fmt_reports = String::new(); // Reports specific to the input formats, like the per-section counts of LaTeX
+    let mut record_counts: Vec<(String, scan::ScanCounts)> = Vec::new(); // Counts of each data field record, exported in place of their files
+    for filename in args.filenames.iter() {
+        documents.extend(read_input(filename, &args, &mut fmt_reports, &mut record_counts)?);
+    }
+    // Every term of a single document occurs in all documents, so its inverse document frequency and every score would be zero
+    if args.tfidf_layout.is_some() && documents.len() < 2 {
+        eprintln!("TF-IDF ranks terms relative to the other inputs and needs at least 2 files, archive members or chapters, got {}.", documents.len());
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "TF-IDF requires at least 2 documents"));
+    }
+
+    // Tally characters before entities are pulled out so emoji and the characters of URLs are included
+    let mut char_counts = CharCounts::default();
+    if let Some(unit) = args.char_unit {
+        documents.iter().for_each(|(_, contents)| char_counts.add_text(contents, unit));
+    }
+
+    // Pull entities out before tokenizing, so URLs and emails are neither mangled into words nor counted as them
+    let mut entity_counts = EntityCounts::default();
+    if let Some(kinds) = args.entity_kinds.as_ref() {
+        for (_, contents) in documents.iter_mut() {
+            *contents = entities::extract(contents, kinds, args.is_cased, &mut entity_counts);
+        }
+    }
+
+    // Count the number of lines in the raw content that are not empty
+    let line_counts: Vec<usize> = documents.iter().map(|(_, contents)| wclib::count_lines(contents)).collect();
+    let line_count: usize = line_counts.iter().sum();
+
+    // Count the word frequencies per document and merged across all documents to display the top N along with the counts
+    let doc_maps: Vec<WordMap> = documents.iter().map(|(_, contents)| wclib::count_words(contents, args.is_cased, args.is_include_all)).collect();
+    let word_map: WordMap = wclib::merge_word_maps(&doc_maps);
+    if args.data_field.is_some() {
+        export_counts(&args, &record_counts)?;
+    } else {
+        let input_counts: Vec<(String, scan::ScanCounts)> = documents.iter().zip(doc_maps.iter()).zip(line_counts.iter())
+            .map(|(((name, _), doc_map), lines)| (name.clone(), scan::ScanCounts { words: doc_map.values().sum(), lines: *lines }))
+            .collect();
+        export_counts(&args, &input_counts)?;
+    }
+
+    // Histogram of word or line lengths, if requested
+    let histogram = args.histogram.map(|kind| {
+        let mut hist = Histogram::new(kind);
+        match kind {
+            HistogramKind::Lines => documents.iter().flat_map(|(_, contents)| contents.lines()).filter(|l| !l.is_empty()).for_each(|l| hist.add(l.chars().count())),
+            HistogramKind::Words => word_map.iter().for_each(|(w, c)| hist.add_n(w.chars().count(), *c)),
+        }
+        hist
+    });
+
     // Get the top N
     let top_n = args.top_words.unwrap_or_default(); // Safe to unwrap since it's checked above
 
@@ -55,15 +639,12 @@ fn main() -> io::Result<()> {
     word_map.iter().for_each(|(_, c)| word_count += *c);
 
     // Generate and print the frequency table
-    let ignore_words = if !args.is_include_stopwords { STOPWORDS.as_ref() } else { &[""] };
     // let fmt_freq_table = wclib::generate_frequency_table(&word_map, top_n, ignore_words);
+    let is_counted = |word: &str| is_counted(word, &args);
 
     // Revised approach using tabulars module
-    let mut sorted_freqs: Vec<(&String, &usize)> = word_map.iter()
-        .filter(|(word, _)| 
-            !ignore_words.contains(&word.to_ascii_lowercase().as_str()) // Passes stopwords check
-                && args.ignore_words.as_ref().map_or(true, |iwords| !iwords.contains(&word)) // Passes additional ignore words check
-        )
+    let mut sorted_freqs: Vec<(&Cow<str>, &usize)> = word_map.iter()
+        .filter(|(word, _)| is_counted(word))
         .collect();
     sorted_freqs.sort_by(|a, b| b.1.cmp(a.1)); // Sort in descending order by frequency
     
@@ -77,22 +658,117 @@ fn main() -> io::Result<()> {
     let alignment = "><>"; 
     // Create the table using the headers and data along with specified style formatting if top_n was provided and greater than 0:
     let fmt_freq_table = if top_n > 0 {
-        wclib::Table::new_with_style(headers, data, style, alignment).to_string()
+        let mut table = wclib::Table::new_with_style(headers, data, style.clone(), alignment);
+        // Add relative magnitude columns based on the count column if requested
+        if args.is_bars {
+            table.add_percent_column(2, word_count);
+            table.add_bar_column(2, 20);
+        }
+        table.to_string()
+    } else {
+        "".to_string()
+    };
+
+    // List each kind of entity in its own table followed by the totals of every kind
+    let fmt_entities = args.entity_kinds.as_ref().map_or_else(String::new, |kinds| {
+        let entity_top_n = if top_n > 0 { top_n } else { 10 };
+        let tables: String = kinds.iter()
+            .filter_map(|kind| entities::generate_entity_table(&entity_counts, *kind, entity_top_n, style.clone()))
+            .map(|table| table.to_string())
+            .collect();
+        let totals: Vec<String> = kinds.iter().map(|kind| format!("{} {}", entity_counts.total(*kind), kind.label())).collect();
+        format!("{tables}{}\n", totals.join(", "))
+    });
+
+    // Character frequencies followed by their breakdown into categories, scripts and punctuation marks
+    let fmt_chars = if args.char_unit.is_some() {
+        let table = chars::generate_char_table(&char_counts, if top_n > 0 { top_n } else { 10 }, style.clone());
+        let fmt_punctuation = chars::generate_punctuation_table(&char_counts, style.clone()).map_or_else(String::new, |table| table.to_string());
+        format!("{table}{}{}{fmt_punctuation}{} characters, {} distinct\n", chars::generate_category_table(&char_counts, style.clone()),
+            chars::generate_script_table(&char_counts, style.clone()), char_counts.total(), char_counts.distinct())
+    } else {
+        "".to_string()
+    };
+
+    // Create the length histogram table if requested, sharing the same style as the frequency table
+    let fmt_histogram = histogram.map_or_else(String::new, |hist| hist.to_table(wclib::TableStyle::Polars).to_string());
+
+    // Fit the rank-frequency curve across all words, including stopwords, since they make up the head of the distribution
+    let fmt_zipf = if args.is_zipf {
+        let ranked = zipf::rank_frequency(&word_map);
+        if let Some(path) = args.zipf_export.as_ref() {
+            let exported = File::create(path).and_then(|file| zipf::export_rank_frequency(&mut io::BufWriter::new(file), &ranked));
+            if let Err(e) = exported {
+                eprintln!("Error exporting rank-frequency pairs to {:?}: {}", path, e);
+                return Err(e);
+            }
+        }
+        let frequencies: Vec<usize> = ranked.iter().map(|(_, c)| *c).collect();
+        match ZipfFit::fit(&frequencies) {
+            Some(fit) => {
+                let table = zipf::generate_deviation_table(&fit, &ranked, if top_n > 0 { top_n } else { 10 }, style.clone());
+                format!("{table}zipf exponent {:.3}, R² {:.3} across {} ranks\n", fit.exponent, fit.r_squared, fit.n_ranks)
+            },
+            None => "zipf fit requires at least 2 distinct words\n".to_string(),
+        }
     } else {
         "".to_string()
     };
 
-    // let fmt_filename = args.filename.to_string_lossy().replace("\\", "/");
-    // let fmt_filename = concat_str!(fmt_filename, ": ");
+    // Count the comparison input with identical options and tabulate the words that changed the most
+    let (fmt_compare, fmt_compare_summary) = if let Some(compare_filename) = args.compare_filename.as_ref() {
+        // Archive members are compared as one concatenated input, format reports are only shown for the counted inputs
+        let compare_contents = read_input(compare_filename, &args, &mut String::new(), &mut Vec::new())?.into_iter().map(|(_, contents)| contents).collect::<Vec<_>>().join("\n");
+        // Entities are removed from the compared input too, but only those of the counted inputs are listed
+        let compare_contents = args.entity_kinds.as_ref().map_or(compare_contents.clone(), |kinds| entities::extract(&compare_contents, kinds, args.is_cased, &mut EntityCounts::default()));
+        let compare_map = wclib::count_words(&compare_contents, args.is_cased, args.is_include_all);
+        let deltas = compare::compare_frequencies(&word_map, &compare_map, is_counted, args.compare_sort);
+        let table = compare::generate_compare_table(&deltas, if top_n > 0 { top_n } else { 10 }, style.clone());
+        let compare_word_count: usize = compare_map.values().sum();
+        (table.to_string(), format!("\nvs {}: {} words, {} lines", compare_filename.display(), compare_word_count, wclib::count_lines(&compare_contents)))
+    } else {
+        ("".to_string(), "".to_string())
+    };
+
+    // Rank the terms most characteristic of each file relative to the rest of the set
+    let fmt_tfidf = if let Some(layout) = args.tfidf_layout {
+        let doc_freqs = tfidf::document_frequencies(&doc_maps);
+        let rankings: Vec<(String, Vec<tfidf::TermScore>)> = documents.iter().zip(doc_maps.iter())
+            .map(|((name, _), doc_map)| (name.clone(), tfidf::rank_terms(doc_map, &doc_freqs, doc_maps.len(), args.tfidf_options, is_counted)))
+            .collect();
+        let tfidf_top_n = if top_n > 0 { top_n } else { 10 };
+        match layout {
+            TfIdfLayout::PerFile => rankings.iter()
+                .map(|(name, scores)| concat_str!(name, ":\n", tfidf::generate_tfidf_table(scores, tfidf_top_n, style.clone()).to_string()))
+                .collect::<String>(),
+            TfIdfLayout::Combined => tfidf::generate_combined_table(&rankings, tfidf_top_n, style.clone()).to_string(),
+        }
+    } else {
+        "".to_string()
+    };
 
     let fmt_unique = if args.is_include_unique {
         format!("{unique_count} unique, ")
     } else {
         "".to_string()
     };
-    let fmt_summary = format!("{} words, {}{} lines", word_count, fmt_unique, line_count);
 
-    println!("{fmt_freq_table}{fmt_summary}");
+    // Summarize each file individually ahead of the overall summary when multiple files are provided
+    let fmt_file_summaries = if documents.len() > 1 {
+        documents.iter().zip(doc_maps.iter()).zip(line_counts.iter())
+            .map(|(((name, _), doc_map), doc_line_count)| {
+                let fmt_doc_unique = if args.is_include_unique { format!("{} unique, ", doc_map.len()) } else { "".to_string() };
+                format!("{}: {} words, {}{} lines\n", name, doc_map.values().sum::<usize>(), fmt_doc_unique, doc_line_count)
+            })
+            .collect::<String>()
+    } else {
+        "".to_string()
+    };
+
+    let fmt_total = if documents.len() > 1 { "total: " } else { "" };
+    let fmt_summary = format!("{}{} words, {}{} lines", fmt_total, word_count, fmt_unique, line_count);
+
+    println!("{fmt_freq_table}{fmt_entities}{fmt_chars}{fmt_histogram}{fmt_zipf}{fmt_compare}{fmt_tfidf}{fmt_reports}{fmt_file_summaries}{fmt_summary}{fmt_compare_summary}");
 
     // Print the word count
     Ok(())
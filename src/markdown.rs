@@ -0,0 +1,82 @@
+use pulldown_cmark::{Event, LinkType, Options, Parser, Tag, TagEnd};
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Selects which non-prose parts of a Markdown document are kept when extracting its text, all of them are skipped by default.
+pub struct MarkdownOptions {
+    pub is_include_code: bool, // Keep fenced and indented code blocks along with inline code spans
+    pub is_include_links: bool, // Keep link and image targets, including autolinks, alongside their text
+    pub is_include_front_matter: bool, // Keep YAML (`---`) or TOML (`+++`) front matter
+    pub is_include_tables: bool, // Keep the text of table cells
+}
+
+/// Extracts the prose of a CommonMark document, dropping syntax like header markers, emphasis and raw HTML along with the parts excluded by `options`.
+/// Each block ends on its own line and soft line breaks are kept, so line counts follow the lines of prose in the source.
+pub fn extract_prose(text: &str, options: MarkdownOptions) -> String {
+    let parser_options = Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS;
+    let mut prose = String::with_capacity(text.len());
+    let mut skip_depth = 0_usize; // Nesting depth inside an excluded element, zero when keeping text
+    let mut link_targets: Vec<String> = Vec::new(); // Targets of the open links, written out once the link text ends
+    for event in Parser::new_ext(text, parser_options) {
+        if skip_depth > 0 {
+            match event {
+                Event::Start(_) => skip_depth += 1,
+                Event::End(_) => skip_depth -= 1,
+                _ => {},
+            }
+            continue;
+        }
+        match event {
+            Event::Start(tag) => match tag {
+                Tag::CodeBlock(_) if !options.is_include_code => skip_depth = 1,
+                Tag::Table(_) if !options.is_include_tables => skip_depth = 1,
+                Tag::MetadataBlock(_) if !options.is_include_front_matter => skip_depth = 1,
+                Tag::HtmlBlock => skip_depth = 1,
+                // The text of an autolink is its target, so it is not prose
+                Tag::Link { link_type: LinkType::Autolink | LinkType::Email, .. } if !options.is_include_links => skip_depth = 1,
+                Tag::Link { link_type: LinkType::Autolink | LinkType::Email, .. } => link_targets.push(String::new()),
+                Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => link_targets.push(dest_url.to_string()),
+                // Nested lists start on a new line rather than running into the text of the parent item
+                Tag::List(_) | Tag::Item if !prose.is_empty() && !prose.ends_with('\n') => prose.push('\n'),
+                _ => {},
+            },
+            Event::End(tag) => match tag {
+                TagEnd::Link | TagEnd::Image => {
+                    if let Some(target) = link_targets.pop().filter(|t| options.is_include_links && !t.is_empty()) {
+                        prose.push(' ');
+                        prose.push_str(&target);
+                    }
+                },
+                TagEnd::TableCell => prose.push(' '),
+                TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::CodeBlock | TagEnd::Item | TagEnd::TableHead | TagEnd::TableRow
+                    | TagEnd::MetadataBlock(_) | TagEnd::FootnoteDefinition | TagEnd::DefinitionListTitle | TagEnd::DefinitionListDefinition => prose.push('\n'),
+                _ => {},
+            },
+            Event::Text(text) => prose.push_str(&text),
+            Event::Code(code) if options.is_include_code => prose.push_str(&code),
+            // Excluded inline code still separates the words on either side of it
+            Event::Code(_) => prose.push(' '),
+            Event::SoftBreak | Event::HardBreak => prose.push('\n'),
+            // Raw HTML, footnote references, rules and task list markers are not prose
+            _ => {},
+        }
+    }
+    prose
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn excluded_inline_code_separates_words() {
+        let prose = extract_prose("foo`x`bar", MarkdownOptions::default());
+        assert_eq!(prose.split_whitespace().collect::<Vec<_>>(), vec!["foo", "bar"]);
+    }
+
+    #[test]
+    fn included_inline_code_is_kept() {
+        let options = MarkdownOptions { is_include_code: true, ..MarkdownOptions::default() };
+        assert_eq!(extract_prose("run `cargo` now", options).trim(), "run cargo now");
+    }
+}
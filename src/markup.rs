@@ -0,0 +1,397 @@
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines the markup languages whose text content can be extracted for counting.
+pub enum MarkupKind {
+    Html, // Case-insensitive tags, void elements, HTML named entities and collapsible whitespace
+    Xml // Case-sensitive tags, only the predefined entities and every element on its own line
+}
+
+/// Named character references decoded in HTML, covering the predefined XML entities along with common punctuation, symbols and Latin-1 letters.
+const HTML_ENTITIES: [(&str, char); 64] = [
+    ("amp", '&'), ("lt", '<'), ("gt", '>'), ("quot", '"'), ("apos", '\''), ("nbsp", '\u{A0}'),
+    ("ndash", '–'), ("mdash", '—'), ("hellip", '…'), ("lsquo", '‘'), ("rsquo", '’'), ("sbquo", '‚'),
+    ("ldquo", '“'), ("rdquo", '”'), ("bdquo", '„'), ("laquo", '«'), ("raquo", '»'), ("lsaquo", '‹'),
+    ("rsaquo", '›'), ("bull", '•'), ("middot", '·'), ("dagger", '†'), ("Dagger", '‡'), ("prime", '′'),
+    ("copy", '©'), ("reg", '®'), ("trade", '™'), ("deg", '°'), ("plusmn", '±'), ("times", '×'),
+    ("divide", '÷'), ("micro", 'µ'), ("para", '¶'), ("sect", '§'), ("cent", '¢'), ("pound", '£'),
+    ("yen", '¥'), ("euro", '€'), ("iexcl", '¡'), ("iquest", '¿'), ("shy", '\u{AD}'), ("zwj", '\u{200D}'),
+    ("zwnj", '\u{200C}'), ("thinsp", '\u{2009}'), ("ensp", '\u{2002}'), ("emsp", '\u{2003}'), ("agrave", 'à'), ("aacute", 'á'),
+    ("acirc", 'â'), ("auml", 'ä'), ("ccedil", 'ç'), ("egrave", 'è'), ("eacute", 'é'), ("ecirc", 'ê'),
+    ("euml", 'ë'), ("iacute", 'í'), ("ntilde", 'ñ'), ("oacute", 'ó'), ("ouml", 'ö'), ("uacute", 'ú'),
+    ("uuml", 'ü'), ("szlig", 'ß'), ("Eacute", 'É'), ("Auml", 'Ä'),
+];
+
+/// HTML elements that never have content or an end tag.
+const HTML_VOID_ELEMENTS: [&str; 14] = ["area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr"];
+
+/// HTML elements whose content is script or styling rather than text.
+const HTML_RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+/// HTML elements rendered inline, whose tags do not separate the surrounding text onto new lines.
+const HTML_INLINE_ELEMENTS: [&str; 24] = ["a", "abbr", "b", "bdi", "bdo", "cite", "code", "data", "dfn", "em", "i", "kbd", "label", "mark", "q", "s", "samp", "small", "span", "strong", "sub", "sup", "time", "u"];
+
+/// HTML elements whose whitespace is preserved rather than collapsed.
+const HTML_PREFORMATTED_ELEMENTS: [&str; 2] = ["pre", "textarea"];
+
+#[derive(Debug, Clone, Default)]
+/// An element along with its attributes, keeping the id and classes separately for selector matching.
+pub struct Element {
+    pub name: String,
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub attributes: Vec<(String, String)>, // All attributes in source order with entities decoded
+}
+
+impl Element {
+    /// Returns the value of the named attribute, if present.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A single piece of a markup document produced by `Tokenizer`, comments, processing instructions and declarations are never produced.
+pub enum MarkupEvent<'a> {
+    Start(Element), // Start tag of an element that has content
+    Empty(Element), // Self-closing tag, or an HTML void element, that has no content or end tag
+    End(String), // End tag, naming the element it closes
+    Text(Cow<'a, str>) // Text with entities decoded, or the raw content of a CDATA section
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines how a selector step relates to the element matched by the previous step.
+enum Combinator {
+    Descendant, // Any ancestor, or anywhere in the document for the first step
+    Child // The direct parent, or the document root for the first step
+}
+
+#[derive(Debug, Clone)]
+/// A single compound selector such as `div`, `p.note`, `#main` or `*`.
+struct SelectorStep {
+    combinator: Combinator,
+    name: Option<String>, // Required element name, any element if `None`
+    id: Option<String>,
+    classes: Vec<String>,
+}
+
+impl SelectorStep {
+    fn matches(&self, element: &Element) -> bool {
+        self.name.as_ref().is_none_or(|name| *name == element.name)
+            && self.id.as_ref().is_none_or(|id| element.id.as_ref() == Some(id))
+            && self.classes.iter().all(|class| element.classes.contains(class))
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Restricts counting to the content of matching elements, parsed from either a simple CSS selector like `article p.note`
+/// with descendant (` `) and child (`>`) combinators, or an XPath-like path like `/book/chapter//para` starting with `/`.
+pub struct Selector {
+    steps: Vec<SelectorStep>,
+}
+
+impl Selector {
+    /// Parses a CSS selector or, if it starts with `/`, an XPath-like path.
+    pub fn parse(selector: &str) -> Result<Self, String> {
+        let selector = selector.trim();
+        let steps = if selector.starts_with('/') { Self::parse_path(selector)? } else { Self::parse_css(selector)? };
+        if steps.is_empty() {
+            return Err("selector is empty".to_string())
+        }
+        Ok(Selector { steps })
+    }
+    fn parse_path(path: &str) -> Result<Vec<SelectorStep>, String> {
+        let mut steps = Vec::new();
+        let mut combinator = Combinator::Child;
+        for segment in path[1..].split('/') {
+            // An empty segment comes from `//`, which selects descendants at any depth
+            if segment.is_empty() {
+                combinator = Combinator::Descendant;
+                continue;
+            }
+            if !segment.chars().all(|c| c.is_alphanumeric() || matches!(c, '*' | '-' | '_' | ':' | '.')) || (segment.contains('*') && segment != "*") {
+                return Err(format!("unsupported path step `{segment}`"))
+            }
+            steps.push(SelectorStep { combinator, name: (segment != "*").then(|| segment.to_string()), id: None, classes: Vec::new() });
+            combinator = Combinator::Child;
+        }
+        Ok(steps)
+    }
+    fn parse_css(css: &str) -> Result<Vec<SelectorStep>, String> {
+        let mut steps = Vec::new();
+        let mut combinator = Combinator::Descendant;
+        for token in css.replace('>', " > ").split_whitespace() {
+            if token == ">" {
+                if steps.is_empty() {
+                    return Err("selector cannot start with `>`".to_string())
+                }
+                combinator = Combinator::Child;
+                continue;
+            }
+            let mut step = SelectorStep { combinator, name: None, id: None, classes: Vec::new() };
+            // Split the compound selector before each `#` or `.` into the name, id and class parts
+            let mut pieces: Vec<(Option<char>, String)> = vec![(None, String::new())];
+            for c in token.chars() {
+                match c {
+                    '#' | '.' => pieces.push((Some(c), String::new())),
+                    _ => if let Some((_, value)) = pieces.last_mut() { value.push(c) },
+                }
+            }
+            for (prefix, value) in pieces {
+                match prefix {
+                    None if value.is_empty() || value == "*" => {},
+                    None => step.name = Some(value),
+                    _ if value.is_empty() => return Err(format!("selector `{token}` has an empty id or class")),
+                    Some('#') => step.id = Some(value),
+                    Some(_) => step.classes.push(value),
+                }
+            }
+            steps.push(step);
+            combinator = Combinator::Descendant;
+        }
+        Ok(steps)
+    }
+    /// Returns true if the last element of `stack` matches the selector given its ancestors in the rest of the stack.
+    pub fn matches(&self, stack: &[Element]) -> bool {
+        !stack.is_empty() && self.matches_at(stack, self.steps.len() - 1, stack.len() - 1)
+    }
+    fn matches_at(&self, stack: &[Element], step: usize, pos: usize) -> bool {
+        let current = &self.steps[step];
+        if !current.matches(&stack[pos]) {
+            return false
+        }
+        match (step, current.combinator) {
+            (0, Combinator::Child) => pos == 0,
+            (0, Combinator::Descendant) => true,
+            (_, Combinator::Child) => pos > 0 && self.matches_at(stack, step - 1, pos - 1),
+            (_, Combinator::Descendant) => (0..pos).rev().any(|p| self.matches_at(stack, step - 1, p)),
+        }
+    }
+}
+
+/// Decodes named and numeric character references, leaving unknown or malformed references as they are.
+pub fn decode_entities(text: &str, kind: MarkupKind) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text)
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = rest[1..].char_indices().take(32).find(|(_, c)| *c == ';').map(|(end, _)| &rest[1..end + 1]);
+        let c = reference.and_then(|r| match r.strip_prefix('#') {
+            Some(numeric) => match numeric.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => numeric.parse::<u32>().ok(),
+            }.and_then(char::from_u32),
+            // XML only predefines the first five entities
+            None => HTML_ENTITIES.iter().take(if kind == MarkupKind::Xml { 5 } else { HTML_ENTITIES.len() }).find(|(name, _)| *name == r).map(|(_, c)| *c),
+        });
+        match (c, reference) {
+            (Some(c), Some(reference)) => {
+                decoded.push(c);
+                rest = &rest[reference.len() + 2..];
+            },
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+    decoded.push_str(rest);
+    Cow::Owned(decoded)
+}
+
+/// Parses the name and attributes from the inside of a start tag, returning the element and whether the tag is self-closing.
+fn parse_start_tag(tag: &str, kind: MarkupKind) -> (Element, bool) {
+    let is_self_closing = tag.ends_with('/');
+    let tag = tag.trim_end_matches('/');
+    let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+    let name = if kind == MarkupKind::Html { tag[..name_end].to_ascii_lowercase() } else { tag[..name_end].to_string() };
+    let mut element = Element { name, ..Default::default() };
+    let mut attributes = tag[name_end..].trim_start();
+    while !attributes.is_empty() {
+        let key_end = attributes.find(|c: char| c == '=' || c.is_whitespace()).unwrap_or(attributes.len());
+        let key = if kind == MarkupKind::Html { attributes[..key_end].to_ascii_lowercase() } else { attributes[..key_end].to_string() };
+        attributes = attributes[key_end..].trim_start();
+        let mut value = "";
+        if let Some(after_eq) = attributes.strip_prefix('=') {
+            let after_eq = after_eq.trim_start();
+            let (raw, rest) = match after_eq.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = after_eq[1..].find(quote).map_or(after_eq.len(), |e| e + 1);
+                    (&after_eq[1..end], after_eq.get(end + 1..).unwrap_or_default())
+                },
+                _ => {
+                    let end = after_eq.find(char::is_whitespace).unwrap_or(after_eq.len());
+                    (&after_eq[..end], &after_eq[end..])
+                },
+            };
+            value = raw;
+            attributes = rest.trim_start();
+        }
+        if key.is_empty() {
+            break;
+        }
+        let value = decode_entities(value, kind).into_owned();
+        match key.as_str() {
+            "id" | "xml:id" => element.id = Some(value.clone()),
+            "class" => element.classes = value.split_whitespace().map(String::from).collect(),
+            _ => {},
+        }
+        element.attributes.push((key, value));
+    }
+    (element, is_self_closing)
+}
+
+/// Returns the byte offset just past `terminator` in `text`, or the end of the text if it never occurs.
+fn skip_past(text: &str, terminator: &str) -> usize {
+    text.find(terminator).map_or(text.len(), |i| i + terminator.len())
+}
+
+/// A lenient pull tokenizer over HTML or XML that never fails on malformed input, treating stray `<` characters as text
+/// and skipping the content of HTML `<script>` and `<style>` elements.
+pub struct Tokenizer<'a> {
+    rest: &'a str,
+    kind: MarkupKind,
+    raw_text_element: Option<String>, // Name of the HTML raw text element whose content is skipped next
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(text: &'a str, kind: MarkupKind) -> Self {
+        Tokenizer { rest: text, kind, raw_text_element: None }
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = MarkupEvent<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(name) = self.raw_text_element.take() {
+            // Skip to the matching end tag without interpreting the content as markup
+            let rest = self.rest;
+            let end = rest.match_indices("</")
+                .find(|(i, _)| rest[i + 2..].get(..name.len()).is_some_and(|n| n.eq_ignore_ascii_case(&name)))
+                .map_or(rest.len(), |(i, _)| i);
+            self.rest = &rest[end..];
+        }
+        loop {
+            let rest = self.rest;
+            if rest.is_empty() {
+                return None
+            }
+            let text_end = rest.find('<').unwrap_or(rest.len());
+            if text_end > 0 {
+                self.rest = &rest[text_end..];
+                return Some(MarkupEvent::Text(decode_entities(&rest[..text_end], self.kind)))
+            }
+            if rest.starts_with("<!--") {
+                self.rest = &rest[skip_past(rest, "-->")..];
+            } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let end = cdata.find("]]>").unwrap_or(cdata.len());
+                self.rest = cdata.get(end + 3..).unwrap_or_default();
+                return Some(MarkupEvent::Text(Cow::Borrowed(&cdata[..end])))
+            } else if rest.starts_with("<?") || rest.starts_with("<!") {
+                // Processing instructions and declarations like `<!DOCTYPE html>` carry no text
+                self.rest = &rest[skip_past(rest, ">")..];
+            } else if let Some(end_tag) = rest.strip_prefix("</") {
+                let tag_end = skip_past(end_tag, ">");
+                let name = end_tag[..tag_end].trim_end_matches('>').trim();
+                self.rest = &end_tag[tag_end..];
+                return Some(MarkupEvent::End(if self.kind == MarkupKind::Html { name.to_ascii_lowercase() } else { name.to_string() }))
+            } else if rest[1..].starts_with(|c: char| c.is_alphabetic() || c == '_') {
+                // Find the end of the start tag, ignoring any `>` inside quoted attribute values
+                let mut quote = None;
+                let tag_end = rest.char_indices().skip(1).find(|(_, c)| match quote {
+                    Some(q) => { if *c == q { quote = None; } false },
+                    None if *c == '"' || *c == '\'' => { quote = Some(*c); false },
+                    None => *c == '>',
+                }).map_or(rest.len(), |(i, _)| i);
+                let (element, is_self_closing) = parse_start_tag(&rest[1..tag_end], self.kind);
+                self.rest = rest.get(tag_end + 1..).unwrap_or_default();
+                let is_html = self.kind == MarkupKind::Html;
+                if is_self_closing || (is_html && HTML_VOID_ELEMENTS.contains(&element.name.as_str())) {
+                    return Some(MarkupEvent::Empty(element))
+                }
+                if is_html && HTML_RAW_TEXT_ELEMENTS.contains(&element.name.as_str()) {
+                    self.raw_text_element = Some(element.name.clone());
+                }
+                return Some(MarkupEvent::Start(element))
+            } else {
+                // A `<` that does not start a tag is literal text
+                self.rest = &rest[1..];
+                return Some(MarkupEvent::Text(Cow::Borrowed("<")))
+            }
+        }
+    }
+}
+
+/// Extracts the text content of an HTML or XML document, decoding entities and skipping tags, comments, processing instructions
+/// and the content of `<script>` and `<style>` elements. If a selector is provided only the content of matching elements is kept.
+/// Block level elements in HTML and every element in XML start on a new line, and blank lines are dropped from the result.
+pub fn extract_text(text: &str, kind: MarkupKind, selector: Option<&Selector>) -> String {
+    let mut extracted = String::with_capacity(text.len() / 2);
+    let mut stack: Vec<Element> = Vec::new();
+    let mut selected_depth: Option<usize> = None; // Stack depth of the outermost selected element, if inside one
+    let mut preformatted_depth = 0_usize;
+    let is_block = |name: &str| kind == MarkupKind::Xml || !HTML_INLINE_ELEMENTS.contains(&name);
+    let is_preformatted = |name: &str| kind == MarkupKind::Html && HTML_PREFORMATTED_ELEMENTS.contains(&name);
+    for event in Tokenizer::new(text, kind) {
+        match event {
+            MarkupEvent::Text(text) if selector.is_none() || selected_depth.is_some() => {
+                if kind == MarkupKind::Html && preformatted_depth == 0 {
+                    // Whitespace in HTML collapses to a single space when rendered
+                    if text.starts_with(char::is_whitespace) {
+                        extracted.push(' ');
+                    }
+                    extracted.push_str(&text.split_whitespace().collect::<Vec<_>>().join(" "));
+                    if text.ends_with(char::is_whitespace) && !text.trim().is_empty() {
+                        extracted.push(' ');
+                    }
+                } else {
+                    extracted.push_str(&text);
+                }
+            },
+            MarkupEvent::Text(_) => {},
+            MarkupEvent::Empty(element) => {
+                if is_block(&element.name) {
+                    extracted.push('\n');
+                }
+            },
+            MarkupEvent::Start(element) => {
+                if is_block(&element.name) {
+                    extracted.push('\n');
+                }
+                if is_preformatted(&element.name) {
+                    preformatted_depth += 1;
+                }
+                stack.push(element);
+                if selected_depth.is_none() && selector.is_some_and(|s| s.matches(&stack)) {
+                    selected_depth = Some(stack.len() - 1);
+                    extracted.push('\n');
+                }
+            },
+            MarkupEvent::End(name) => {
+                // Close the nearest matching element along with any unclosed elements inside it, ignoring stray end tags
+                let Some(pos) = stack.iter().rposition(|element| element.name == name) else {
+                    continue;
+                };
+                for element in stack.drain(pos..) {
+                    if is_preformatted(&element.name) {
+                        preformatted_depth -= 1;
+                    }
+                    if is_block(&element.name) {
+                        extracted.push('\n');
+                    }
+                }
+                // Each selected element ends on its own line so adjacent matches never run together
+                if selected_depth.is_some_and(|depth| depth >= stack.len()) {
+                    selected_depth = None;
+                    extracted.push('\n');
+                }
+            },
+        }
+    }
+    extracted.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join("\n")
+}
@@ -0,0 +1,229 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use memmap2::Mmap;
+
+/// Number of bytes classified together into 64-bit masks.
+const BLOCK_SIZE: usize = 64;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Word and non-empty line totals produced by the byte scanner, matching `count_words` and `count_lines` exactly.
+pub struct ScanCounts {
+    pub words: usize,
+    pub lines: usize,
+}
+
+impl std::ops::AddAssign for ScanCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.words += other.words;
+        self.lines += other.lines;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Per block classification of bytes into bitmasks, bit `i` corresponding to byte `i` of the block.
+struct BlockMasks {
+    whitespace: u64, // Bytes starting a whitespace character
+    content: u64, // Bytes starting a character that keeps a token from being empty after normalization
+    newline: u64, // `\n` bytes
+    carriage: u64, // `\r` bytes
+    non_ascii: u64, // Bytes with the high bit set, i.e. part of a multibyte character
+    continuation: u64, // Trailing bytes of multibyte characters, which never start a character
+}
+
+/// Memory maps the file for reading, returning `None` for empty files which cannot be mapped on some platforms.
+pub fn map_file(path: &Path) -> io::Result<Option<Mmap>> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(None)
+    }
+    // SAFETY: the map is read-only, concurrent truncation by another process is the usual mmap caveat
+    let mmap = unsafe { Mmap::map(&file)? };
+    Ok(Some(mmap))
+}
+
+/// Memory maps the file and counts its words and non-empty lines with the vectorized scanner, failing on invalid UTF-8 like `read_to_string` does.
+pub fn scan_file(path: &Path, is_include_all: bool) -> io::Result<ScanCounts> {
+    let Some(mmap) = map_file(path)? else {
+        return Ok(ScanCounts::default())
+    };
+    let text = std::str::from_utf8(&mmap).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(scan_counts(text, is_include_all))
+}
+
+/// Writes the word and non-empty line counts of each named input as CSV, one row per file, archive member, chapter or record.
+pub fn export_counts<W: Write>(writer: &mut W, counts: &[(String, ScanCounts)]) -> io::Result<()> {
+    writeln!(writer, "input,words,lines")?;
+    for (name, counts) in counts.iter() {
+        // Quote names containing delimiters, quotes or line breaks, which can occur in archive member and record names
+        if name.contains([',', '"', '\n', '\r']) {
+            writeln!(writer, "\"{}\",{},{}", name.replace('"', "\"\""), counts.words, counts.lines)?;
+        } else {
+            writeln!(writer, "{},{},{}", name, counts.words, counts.lines)?;
+        }
+    }
+    Ok(())
+}
+
+/// Counts the words and non-empty lines in the text a block of 64 bytes at a time using bitmask arithmetic instead of per token allocation.
+/// A word is a whitespace separated token containing at least one alphanumeric character or apostrophe, or any token at all if `is_include_all`.
+pub fn scan_counts(text: &str, is_include_all: bool) -> ScanCounts {
+    let bytes = text.as_bytes();
+    let mut counts = ScanCounts::default();
+    // Carried state across blocks, the start of the input behaves as if preceded by whitespace and newlines
+    let mut after_whitespace = 1_u64; // 1 if the last non-neutral byte seen was whitespace
+    let mut prev_newline = 0b11_u64 << 62;
+    let mut prev_carriage = 0_u64;
+
+    for (block_id, block) in bytes.chunks(BLOCK_SIZE).enumerate() {
+        let offset = block_id * BLOCK_SIZE;
+        let mut masks = classify_block(block);
+        if masks.non_ascii != 0 {
+            classify_non_ascii(text, offset, masks.non_ascii, &mut masks);
+        }
+        if is_include_all {
+            masks.content = !(masks.whitespace | masks.continuation);
+        }
+        // Restrict the masks to the bytes actually present in a trailing partial block
+        let valid = if block.len() == BLOCK_SIZE { u64::MAX } else { (1_u64 << block.len()) - 1 };
+        let whitespace = masks.whitespace & valid;
+        let content = masks.content & valid & !whitespace;
+        let neutral = valid & !(whitespace | content);
+
+        // Smear whitespace forward through runs of neutral bytes (punctuation, continuation bytes) using carry propagation,
+        // so a content byte starts a new word exactly when the nearest preceding non-neutral byte is whitespace
+        let run_starts = ((whitespace << 1) | after_whitespace) & neutral;
+        let reached = whitespace | (neutral & !neutral.wrapping_add(run_starts));
+        let word_starts = content & ((reached << 1) | after_whitespace);
+        counts.words += word_starts.count_ones() as usize;
+        if block.len() == BLOCK_SIZE {
+            after_whitespace = reached >> 63;
+        } else {
+            after_whitespace = (reached >> (block.len() - 1)) & 1;
+        }
+
+        // A line is empty when its newline directly follows another newline, or follows a carriage return which itself follows a newline
+        let newline = masks.newline & valid;
+        let carriage = masks.carriage & valid;
+        let prev_is_newline = (newline << 1) | (prev_newline >> 63);
+        let prev_is_carriage = (carriage << 1) | (prev_carriage >> 63);
+        let prev2_is_newline = (newline << 2) | (prev_newline >> 62);
+        let empty = newline & (prev_is_newline | (prev_is_carriage & prev2_is_newline));
+        counts.lines += (newline & !empty).count_ones() as usize;
+        prev_newline = newline << (BLOCK_SIZE - block.len());
+        prev_carriage = carriage << (BLOCK_SIZE - block.len());
+    }
+
+    // The final line only counts if it has content after the last newline
+    let last_newline = bytes.iter().rposition(|b| *b == b'\n');
+    if last_newline.map_or(!bytes.is_empty(), |pos| pos + 1 < bytes.len()) {
+        counts.lines += 1;
+    }
+    counts
+}
+
+/// Classifies the multibyte characters starting within the block, marking continuation bytes so they stay neutral.
+fn classify_non_ascii(text: &str, offset: usize, mut high: u64, masks: &mut BlockMasks) {
+    while high != 0 {
+        let i = high.trailing_zeros() as usize;
+        high &= high - 1;
+        if !text.is_char_boundary(offset + i) {
+            masks.continuation |= 1 << i;
+        } else if let Some(c) = text[offset + i..].chars().next() {
+            if c.is_whitespace() {
+                masks.whitespace |= 1 << i;
+            } else if c.is_alphanumeric() {
+                masks.content |= 1 << i;
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+/// Classifies the ASCII bytes of a block 16 bytes at a time using SSE2, which every x86_64 target supports.
+fn classify_block(block: &[u8]) -> BlockMasks {
+    use std::arch::x86_64::*;
+
+    // Pad partial blocks with spaces, the padding is masked out by the caller
+    let mut buffer = [b' '; BLOCK_SIZE];
+    buffer[..block.len()].copy_from_slice(block);
+    let mut masks = BlockMasks::default();
+    for lane in 0..BLOCK_SIZE / 16 {
+        // SAFETY: SSE2 is part of the x86_64 baseline and the unaligned load reads 16 bytes within `buffer`
+        let (whitespace, content, newline, carriage, non_ascii) = unsafe {
+            let v = _mm_loadu_si128(buffer.as_ptr().add(lane * 16) as *const __m128i);
+            // Signed comparisons are safe for ranges within ASCII since non-ASCII bytes compare as negative
+            let in_range = |lo: u8, hi: u8| _mm_and_si128(_mm_cmpgt_epi8(v, _mm_set1_epi8(lo as i8 - 1)), _mm_cmplt_epi8(v, _mm_set1_epi8(hi as i8 + 1)));
+            let eq = |b: u8| _mm_cmpeq_epi8(v, _mm_set1_epi8(b as i8));
+            let whitespace = _mm_or_si128(in_range(b'\t', b'\r'), eq(b' '));
+            let content = _mm_or_si128(_mm_or_si128(in_range(b'0', b'9'), in_range(b'A', b'Z')), _mm_or_si128(in_range(b'a', b'z'), eq(b'\'')));
+            (_mm_movemask_epi8(whitespace), _mm_movemask_epi8(content), _mm_movemask_epi8(eq(b'\n')), _mm_movemask_epi8(eq(b'\r')), _mm_movemask_epi8(v))
+        };
+        let shift = lane * 16;
+        masks.whitespace |= (whitespace as u16 as u64) << shift;
+        masks.content |= (content as u16 as u64) << shift;
+        masks.newline |= (newline as u16 as u64) << shift;
+        masks.carriage |= (carriage as u16 as u64) << shift;
+        masks.non_ascii |= (non_ascii as u16 as u64) << shift;
+    }
+    masks
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+/// Classifies the ASCII bytes of a block one byte at a time on targets without the SSE2 path.
+fn classify_block(block: &[u8]) -> BlockMasks {
+    let mut masks = BlockMasks::default();
+    for (i, b) in block.iter().enumerate() {
+        let bit = 1_u64 << i;
+        if matches!(b, b'\t'..=b'\r' | b' ') {
+            masks.whitespace |= bit;
+        }
+        if b.is_ascii_alphanumeric() || *b == b'\'' {
+            masks.content |= bit;
+        }
+        if *b == b'\n' {
+            masks.newline |= bit;
+        }
+        if *b == b'\r' {
+            masks.carriage |= bit;
+        }
+        if !b.is_ascii() {
+            masks.non_ascii |= bit;
+        }
+    }
+    masks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts that the scanner agrees with the token based counters in both word modes.
+    fn assert_matches_counters(text: &str) {
+        for is_include_all in [false, true] {
+            let words: usize = crate::count_words(text, true, is_include_all).values().sum();
+            let expected = ScanCounts { words, lines: crate::count_lines(text) };
+            assert_eq!(scan_counts(text, is_include_all), expected, "is_include_all {} on {:?}", is_include_all, text);
+        }
+    }
+
+    #[test]
+    fn matches_counters_on_mixed_text() {
+        for text in ["", "one", "  two  words  ", "a\n\nb\r\n\r\nc\n", "-- ... 'tis «naïve» café — 東京 ok", "x\u{3000}y\u{a0}z\u{2028}w", "emoji 🎉 only 🎉🎉"] {
+            assert_matches_counters(text);
+        }
+    }
+
+    #[test]
+    fn matches_counters_across_block_boundaries() {
+        let base = "word ü—café «x» 東京\u{3000}... ok\r\n\n";
+        for shift in 0..BLOCK_SIZE {
+            // Shifting the text moves multibyte characters, whitespace runs and line breaks across each 64 byte boundary
+            let text = format!("{}{}", "a".repeat(shift), base.repeat(5));
+            assert_matches_counters(&text);
+            let text = format!("{}{}", " ".repeat(shift), base.repeat(5));
+            assert_matches_counters(&text);
+        }
+    }
+}
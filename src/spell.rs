@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::encoding::{self, Encoding};
+use crate::{Table, TableStyle};
+
+/// An affix flag, a character or number depending on the `FLAG` type of the affix file.
+type Flag = u32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines how flags are written in the affix and dictionary files.
+enum FlagKind {
+    Char, // One character per flag, the default and the same as `FLAG UTF-8`
+    Long, // Two characters per flag
+    Numeric // Comma-separated decimal numbers
+}
+
+impl FlagKind {
+    /// Parses a run of flags like `SM`, `AaBb` or `101,202`.
+    fn parse(&self, flags: &str) -> Vec<Flag> {
+        match self {
+            Self::Char => flags.chars().map(|c| c as Flag).collect(),
+            Self::Long => flags.chars().collect::<Vec<_>>().chunks(2).map(|pair| pair.iter().fold(0, |flag, c| (flag << 16) | *c as Flag)).collect(),
+            Self::Numeric => flags.split(',').filter_map(|n| n.trim().parse().ok()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// One position of an affix condition, either any character or a bracketed set of characters that may be negated.
+enum ConditionPart {
+    Any,
+    Set(bool, Vec<char>)
+}
+
+impl ConditionPart {
+    /// Returns true if the character satisfies this position of the condition.
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Set(is_negated, chars) => chars.contains(&c) != *is_negated,
+        }
+    }
+}
+
+/// Parses an affix condition like `[^aeiou]y` into its positions, where `.` alone means no condition.
+fn parse_condition(condition: &str) -> Vec<ConditionPart> {
+    let mut parts = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => parts.push(ConditionPart::Any),
+            '[' => {
+                let set: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                match set.strip_prefix('^') {
+                    Some(set) => parts.push(ConditionPart::Set(true, set.chars().collect())),
+                    None => parts.push(ConditionPart::Set(false, set.chars().collect())),
+                }
+            },
+            c => parts.push(ConditionPart::Set(false, vec![c])),
+        }
+    }
+    if parts == [ConditionPart::Any] { Vec::new() } else { parts }
+}
+
+#[derive(Debug, Clone)]
+/// A prefix or suffix rule removing `strip` from a stem matching `condition` and adding `add` in its place.
+struct Affix {
+    flag: Flag,
+    is_cross_product: bool, // Whether the rule combines with a prefix or suffix that also allows it
+    strip: String,
+    add: String,
+    condition: Vec<ConditionPart>,
+}
+
+impl Affix {
+    /// Returns the stem a word with this suffix was derived from, if the word ends with the suffix and the stem satisfies the condition.
+    fn strip_suffix(&self, word: &str) -> Option<String> {
+        let base = word.strip_suffix(self.add.as_str()).filter(|base| !base.is_empty())?;
+        let stem = format!("{base}{}", self.strip);
+        let tail: Vec<char> = stem.chars().rev().take(self.condition.len()).collect();
+        let is_match = tail.len() == self.condition.len() && self.condition.iter().rev().zip(tail).all(|(part, c)| part.matches(c));
+        is_match.then_some(stem)
+    }
+    /// Returns the stem a word with this prefix was derived from, if the word starts with the prefix and the stem satisfies the condition.
+    fn strip_prefix(&self, word: &str) -> Option<String> {
+        let base = word.strip_prefix(self.add.as_str()).filter(|base| !base.is_empty())?;
+        let stem = format!("{}{base}", self.strip);
+        let head: Vec<char> = stem.chars().take(self.condition.len()).collect();
+        let is_match = head.len() == self.condition.len() && self.condition.iter().zip(head).all(|(part, c)| part.matches(c));
+        is_match.then_some(stem)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A Hunspell style dictionary of stems with affix flags, along with the prefix and suffix rules of its affix file.
+/// Only single prefix and suffix derivations are supported, compounding and two-level suffixes are not.
+pub struct Dictionary {
+    words: HashMap<String, Vec<Flag>>,
+    prefixes: Vec<Affix>,
+    suffixes: Vec<Affix>,
+    need_affix: Option<Flag>, // Stems that are only words with an affix
+    forbidden: Option<Flag>, // Words that are always misspelled, even if derivable
+    only_in_compound: Option<Flag>, // Stems that are only words inside compounds, which are not supported
+}
+
+/// Reads a dictionary or affix file in the character set named by the affix file.
+fn read_dictionary_file(path: &Path, encoding: Encoding) -> io::Result<String> {
+    let bytes = fs::read(path)?;
+    Ok(encoding::decode(&bytes, Some(encoding)).text.into_owned())
+}
+
+impl Dictionary {
+    /// Loads a `.dic` file along with the `.aff` file of the same name next to it.
+    pub fn load(dic_path: &Path) -> io::Result<Self> {
+        let aff_path = dic_path.with_extension("aff");
+        // The character set is named inside the affix file itself, which is ASCII up to that point
+        let aff_bytes = fs::read(&aff_path)?;
+        let charset = encoding::decode(&aff_bytes, Some(Encoding::Latin1)).text.lines()
+            .find_map(|line| line.strip_prefix("SET ").map(|set| set.trim().to_ascii_lowercase()))
+            .unwrap_or_else(|| "utf-8".to_string());
+        let encoding = match charset.as_str() {
+            "iso8859-1" | "iso-8859-1" => Encoding::Latin1,
+            charset => Encoding::from_label(charset).filter(|e| matches!(e, Encoding::Utf8 | Encoding::Windows1252))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("unsupported dictionary character set {charset:?}")))?,
+        };
+
+        Ok(Self::parse(&read_dictionary_file(&aff_path, encoding)?, &read_dictionary_file(dic_path, encoding)?))
+    }
+    /// Builds a dictionary from the decoded text of its affix and dictionary files.
+    fn parse(aff: &str, dic: &str) -> Self {
+        let mut dictionary = Dictionary::default();
+        let flag_kind = dictionary.parse_affixes(aff);
+        for line in dic.lines().skip(1) {
+            // Entries may be followed by morphological fields after whitespace
+            let Some(entry) = line.split_whitespace().next() else {
+                continue
+            };
+            let (word, flags) = match entry.split_once('/') {
+                Some((word, flags)) => (word, flag_kind.parse(flags)),
+                None => (entry, Vec::new()),
+            };
+            dictionary.words.entry(word.to_string()).or_default().extend(flags);
+        }
+        dictionary
+    }
+    /// Parses the affix rules and special flags of an affix file, returning the flag type used by the dictionary file.
+    fn parse_affixes(&mut self, aff: &str) -> FlagKind {
+        let mut flag_kind = FlagKind::Char;
+        let mut cross_products: HashMap<(bool, Flag), bool> = HashMap::new();
+        for line in aff.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["FLAG", "long"] => flag_kind = FlagKind::Long,
+                ["FLAG", "num"] => flag_kind = FlagKind::Numeric,
+                ["NEEDAFFIX" | "PSEUDOROOT", flag] => self.need_affix = flag_kind.parse(flag).first().copied(),
+                ["FORBIDDENWORD", flag] => self.forbidden = flag_kind.parse(flag).first().copied(),
+                ["ONLYINCOMPOUND", flag] => self.only_in_compound = flag_kind.parse(flag).first().copied(),
+                [kind @ ("PFX" | "SFX"), flag, cross_product @ ("Y" | "N"), count] if count.parse::<usize>().is_ok() => {
+                    if let Some(flag) = flag_kind.parse(flag).first() {
+                        cross_products.insert((*kind == "PFX", *flag), *cross_product == "Y");
+                    }
+                },
+                [kind @ ("PFX" | "SFX"), flag, strip, add, rest @ ..] => {
+                    let Some(flag) = flag_kind.parse(flag).first().copied() else {
+                        continue
+                    };
+                    let is_prefix = *kind == "PFX";
+                    // Continuation flags after the added text would allow a second affix, which is not supported
+                    let add = add.split('/').next().unwrap_or_default();
+                    let affix = Affix {
+                        flag,
+                        is_cross_product: cross_products.get(&(is_prefix, flag)).copied().unwrap_or(false),
+                        strip: if *strip == "0" { String::new() } else { strip.to_string() },
+                        add: if add == "0" { String::new() } else { add.to_string() },
+                        condition: parse_condition(rest.first().copied().unwrap_or(".")),
+                    };
+                    if is_prefix { self.prefixes.push(affix) } else { self.suffixes.push(affix) }
+                },
+                _ => (),
+            }
+        }
+        flag_kind
+    }
+    /// Adds the words of a personal word list, one per line, skipping blank lines and `#` comments.
+    pub fn add_personal_words(&mut self, path: &Path) -> io::Result<()> {
+        let bytes = fs::read(path)?;
+        for word in encoding::decode(&bytes, None).text.lines().map(str::trim).filter(|w| !w.is_empty() && !w.starts_with('#')) {
+            self.words.entry(word.to_string()).or_default();
+        }
+        Ok(())
+    }
+    /// Returns true if the stem is in the dictionary with all of the flags.
+    fn has_stem(&self, stem: &str, flags: &[Flag]) -> bool {
+        self.words.get(stem).is_some_and(|stem_flags| {
+            flags.iter().all(|flag| stem_flags.contains(flag)) && !self.forbidden.is_some_and(|f| stem_flags.contains(&f))
+        })
+    }
+    /// Returns true if the word is a stem of the dictionary or derived from one with a single prefix, suffix or both.
+    fn check_exact(&self, word: &str) -> bool {
+        if let Some(flags) = self.words.get(word) {
+            if self.forbidden.is_some_and(|f| flags.contains(&f)) {
+                return false
+            }
+            if ![self.need_affix, self.only_in_compound].into_iter().flatten().any(|f| flags.contains(&f)) {
+                return true
+            }
+        }
+        let is_suffixed = self.suffixes.iter().any(|suffix| suffix.strip_suffix(word).is_some_and(|stem| {
+            self.has_stem(&stem, &[suffix.flag]) || (suffix.is_cross_product && self.prefixes.iter()
+                .filter(|prefix| prefix.is_cross_product)
+                .any(|prefix| prefix.strip_prefix(&stem).is_some_and(|root| self.has_stem(&root, &[prefix.flag, suffix.flag]))))
+        }));
+        is_suffixed || self.prefixes.iter().any(|prefix| prefix.strip_prefix(word).is_some_and(|stem| self.has_stem(&stem, &[prefix.flag])))
+    }
+    /// Returns true if the word is spelled correctly, also accepting capitalized and uppercase forms of lowercase words
+    /// and uppercase forms of capitalized words like proper nouns.
+    pub fn check(&self, word: &str) -> bool {
+        if self.check_exact(word) {
+            return true
+        }
+        let lower = word.to_lowercase();
+        if lower == word {
+            return false
+        }
+        let mut chars = lower.chars();
+        let capitalized: String = chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default();
+        self.check_exact(&lower) || (capitalized != word && self.check_exact(&capitalized))
+    }
+}
+
+/// Splits a line into the words to spell check, trimming apostrophes at either end and skipping whitespace separated tokens
+/// that look like URLs or email addresses, as well as words containing digits or underscores.
+pub fn words(line: &str) -> impl Iterator<Item = String> + '_ {
+    line.split_whitespace()
+        .filter(|token| !token.contains("://") && !token.contains('@'))
+        .flat_map(|token| token.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '\'' || c == '’')))
+        .map(|word| word.trim_matches(['\'', '’']).replace('’', "'"))
+        .filter(|word| !word.is_empty() && word.chars().all(|c| c.is_alphabetic() || c == '\''))
+}
+
+#[derive(Debug, Clone, Default)]
+/// Occurrences of each misspelled word with the input and line each was found on, merged across inputs.
+pub struct Misspellings {
+    inputs: Vec<String>,
+    locations: HashMap<String, Vec<(usize, usize)>>, // Index into `inputs` and 1-based line number of each occurrence
+    checked: HashMap<String, bool>, // Whether each distinct word was spelled correctly, to check it only once
+}
+
+impl Misspellings {
+    /// Spell checks every word of the text, recording the line of each misspelling. Words for which `is_ignored` returns true are accepted.
+    pub fn check_text(&mut self, name: &str, text: &str, dictionary: &Dictionary, is_ignored: impl Fn(&str) -> bool) {
+        let input = self.inputs.len();
+        self.inputs.push(name.to_string());
+        for (i, line) in text.lines().enumerate() {
+            for word in words(line) {
+                let is_correct = match self.checked.get(&word) {
+                    Some(is_correct) => *is_correct,
+                    None => {
+                        let is_correct = is_ignored(&word) || dictionary.check(&word);
+                        self.checked.insert(word.clone(), is_correct);
+                        is_correct
+                    },
+                };
+                if !is_correct {
+                    self.locations.entry(word).or_default().push((input, i + 1));
+                }
+            }
+        }
+    }
+    /// Returns the total number of misspelled words.
+    pub fn total(&self) -> usize {
+        self.locations.values().map(Vec::len).sum()
+    }
+    /// Returns the number of distinct misspelled words.
+    pub fn distinct(&self) -> usize {
+        self.locations.len()
+    }
+    /// Returns the number of inputs with at least one misspelling, out of all inputs checked.
+    pub fn flagged_inputs(&self) -> (usize, usize) {
+        let mut flagged: Vec<usize> = self.locations.values().flatten().map(|(input, _)| *input).collect();
+        flagged.sort_unstable();
+        flagged.dedup();
+        (flagged.len(), self.inputs.len())
+    }
+    /// Formats the locations of a misspelling like `notes.md:3,12 todo.md:7`.
+    fn fmt_locations(&self, locations: &[(usize, usize)]) -> String {
+        let mut fmt = String::new();
+        for (i, (input, line)) in locations.iter().enumerate() {
+            if i > 0 && locations[i - 1].0 == *input {
+                fmt.push_str(&format!(",{line}"));
+            } else {
+                let separator = if i > 0 { " " } else { "" };
+                fmt.push_str(&format!("{separator}{}:{line}", self.inputs[*input]));
+            }
+        }
+        fmt
+    }
+}
+
+/// Create a table of every misspelled word by descending count, with the input and line numbers of its occurrences.
+pub fn generate_misspelling_table(misspellings: &Misspellings, style: TableStyle) -> Table<String, String> {
+    let mut ranked: Vec<(&String, &Vec<(usize, usize)>)> = misspellings.locations.iter().collect();
+    ranked.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(b.0)));
+    let headers = vec!["#", "word", "ct", "lines"].into_iter().map(String::from).collect();
+    let data = ranked.into_iter().enumerate()
+        .map(|(i, (word, locations))| vec![(i + 1).to_string(), word.clone(), locations.len().to_string(), misspellings.fmt_locations(locations)])
+        .collect();
+    Table::new_with_style(headers, data, style, "><><")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8
+NEEDAFFIX X
+FORBIDDENWORD !
+
+SFX S Y 3
+SFX S 0 s [^sxzhy]
+SFX S 0 s [aeiou]y
+SFX S y ies [^aeiou]y
+
+SFX A Y 1
+SFX A 0 able .
+
+PFX U Y 1
+PFX U 0 un .
+
+PFX R N 1
+PFX R 0 re .
+";
+
+    const DIC: &str = "8
+cat/S
+fly/S
+day/S
+dog/S
+dogs/!
+do/UAR
+walk/XS
+Paris
+";
+
+    #[test]
+    fn suffix_conditions_select_the_rule() {
+        let dictionary = Dictionary::parse(AFF, DIC);
+        assert!(dictionary.check("cat") && dictionary.check("cats"));
+        assert!(dictionary.check("flies") && dictionary.check("days"));
+        assert!(!dictionary.check("dayies") && !dictionary.check("flys") && !dictionary.check("cow"));
+        assert_eq!(parse_condition("[^aeiou]y"), [ConditionPart::Set(true, vec!['a', 'e', 'i', 'o', 'u']), ConditionPart::Set(false, vec!['y'])]);
+        assert_eq!(parse_condition("."), []);
+    }
+
+    #[test]
+    fn prefixes_combine_with_suffixes_only_as_cross_products() {
+        let dictionary = Dictionary::parse(AFF, DIC);
+        assert!(dictionary.check("undo") && dictionary.check("doable") && dictionary.check("undoable"));
+        assert!(dictionary.check("redo"));
+        assert!(!dictionary.check("redoable"));
+    }
+
+    #[test]
+    fn forbidden_and_affix_only_stems() {
+        let dictionary = Dictionary::parse(AFF, DIC);
+        // `dogs` is derivable from `dog/S`, but the forbidden entry wins
+        assert!(dictionary.check("dog") && !dictionary.check("dogs") && !dictionary.check("Dogs"));
+        assert!(dictionary.check("walks"));
+        assert!(!dictionary.check("walk"));
+    }
+
+    #[test]
+    fn capitalized_and_uppercase_forms() {
+        let dictionary = Dictionary::parse(AFF, DIC);
+        assert!(dictionary.check("Cats") && dictionary.check("CATS"));
+        assert!(dictionary.check("Paris") && dictionary.check("PARIS"));
+        assert!(!dictionary.check("paris"));
+    }
+
+    #[test]
+    fn long_and_numeric_flags() {
+        let dictionary = Dictionary::parse("FLAG long\nSFX Aa Y 1\nSFX Aa 0 ing .\nSFX Bb Y 1\nSFX Bb 0 ed .\n", "2\njump/Aa\nplay/AaBb\n");
+        assert!(dictionary.check("jumping") && !dictionary.check("jumped"));
+        assert!(dictionary.check("playing") && dictionary.check("played"));
+        let dictionary = Dictionary::parse("FLAG num\nPFX 101 Y 1\nPFX 101 0 pre .\n", "1\nview/101,202\n");
+        assert!(dictionary.check("preview") && !dictionary.check("review"));
+    }
+
+    #[test]
+    fn words_skip_links_and_tokens_with_digits() {
+        let line = "Don't visit http://x.y or me@x.org, it’s a 3rd_party 'well-known' tool";
+        assert_eq!(words(line).collect::<Vec<_>>(), ["Don't", "visit", "or", "it's", "a", "well", "known", "tool"]);
+    }
+
+    #[test]
+    fn misspellings_are_located_by_input_and_line() {
+        let dictionary = Dictionary::parse(AFF, DIC);
+        let mut misspellings = Misspellings::default();
+        misspellings.check_text("a.md", "cats\ndogz cat\n\ndogz", &dictionary, |_| false);
+        misspellings.check_text("b.md", "cat dogs", &dictionary, |word| word == "dogs");
+        misspellings.check_text("c.md", "dogz", &dictionary, |_| false);
+        assert_eq!((misspellings.total(), misspellings.distinct(), misspellings.flagged_inputs()), (3, 1, (2, 3)));
+        assert_eq!(misspellings.fmt_locations(&misspellings.locations["dogz"]), "a.md:2,4 c.md:1");
+    }
+}
@@ -0,0 +1,244 @@
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A single step of a JSONPath expression.
+enum Segment {
+    Key(String), // `.name` or `['name']`
+    Index(i64), // `[2]`, negative indexes count from the end
+    Wildcard, // `.*` or `[*]`, every member of an object or element of an array
+    Descendants, // `..`, the current value and every value nested in it
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A JSONPath expression like `$.items[*].description` selecting the values to count, supporting keys, indexes, wildcards and `..`.
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    /// Parses a JSONPath expression, where the leading `$` is optional.
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let path = path.trim();
+        let mut rest = path.strip_prefix('$').unwrap_or(path);
+        let mut segments = Vec::new();
+        // Paths without `$` may start directly with a key like `items[*].description`
+        if !path.starts_with(['$', '.', '[']) {
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            segments.push(Segment::Key(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("..") {
+                segments.push(Segment::Descendants);
+                // The step after `..` may also be a bracket
+                rest = if after.starts_with('[') { after } else { &rest[1..] };
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                match &after[..end] {
+                    "" => return Err(format!("expected a key after `.` in {path:?}")),
+                    "*" => segments.push(Segment::Wildcard),
+                    key => segments.push(Segment::Key(key.to_string())),
+                }
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = if after.starts_with(['\'', '"']) {
+                    let quote = &after[..1];
+                    after[1..].find(quote).map(|i| i + 2).filter(|i| after[*i..].starts_with(']'))
+                } else {
+                    after.find(']')
+                }.ok_or_else(|| format!("unclosed `[` in {path:?}"))?;
+                let inner = after[..end].trim();
+                segments.push(match inner {
+                    "*" => Segment::Wildcard,
+                    _ if inner.starts_with(['\'', '"']) => {
+                        // Whitespace before the quote skips the scan for the closing quote above, so the key may be unterminated
+                        if inner.len() < 2 || !inner.ends_with(&inner[..1]) {
+                            return Err(format!("unterminated quoted key in {path:?}"))
+                        }
+                        Segment::Key(inner[1..inner.len() - 1].to_string())
+                    },
+                    _ => Segment::Index(inner.parse().map_err(|_| format!("invalid index `{inner}` in {path:?}"))?),
+                });
+                rest = &after[end + 1..];
+            } else {
+                return Err(format!("expected `.` or `[` at {rest:?} in {path:?}"));
+            }
+        }
+        Ok(Self { segments })
+    }
+    /// Returns the values matching the path along with their concrete paths like `$.items[0].description`, in document order.
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<(String, &'a Value)> {
+        let mut current = vec![("$".to_string(), root)];
+        for segment in self.segments.iter() {
+            let mut next = Vec::new();
+            for (path, value) in current {
+                match segment {
+                    Segment::Key(key) => if let Some(child) = value.get(key) {
+                        next.push((fmt_key_path(&path, key), child));
+                    },
+                    Segment::Index(index) => if let Value::Array(items) = value {
+                        let i = if *index < 0 { items.len() as i64 + index } else { *index };
+                        if let Some(child) = usize::try_from(i).ok().and_then(|i| items.get(i)) {
+                            next.push((format!("{path}[{i}]"), child));
+                        }
+                    },
+                    Segment::Wildcard => next.extend(children(&path, value)),
+                    Segment::Descendants => collect_descendants(path, value, &mut next),
+                }
+            }
+            current = next;
+        }
+        current
+    }
+}
+
+/// Appends an object key to a concrete path, using bracket notation for keys that are not plain identifiers.
+fn fmt_key_path(path: &str, key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+        format!("{path}.{key}")
+    } else {
+        format!("{path}['{key}']")
+    }
+}
+
+/// Returns the members of an object or the elements of an array with their concrete paths.
+fn children<'a>(path: &str, value: &'a Value) -> Vec<(String, &'a Value)> {
+    match value {
+        Value::Object(members) => members.iter().map(|(key, child)| (fmt_key_path(path, key), child)).collect(),
+        Value::Array(items) => items.iter().enumerate().map(|(i, child)| (format!("{path}[{i}]"), child)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Collects the value and every value nested in it in document order.
+fn collect_descendants<'a>(path: String, value: &'a Value, values: &mut Vec<(String, &'a Value)>) {
+    let nested = children(&path, value);
+    values.push((path, value));
+    for (child_path, child) in nested {
+        collect_descendants(child_path, child, values);
+    }
+}
+
+/// Returns the countable text of a selected value: strings as they are, numbers and booleans as written, and the text of every
+/// string, number and boolean nested in arrays and objects on separate lines.
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(_) | Value::Object(_) => children("", value).into_iter()
+            .map(|(_, child)| value_text(child))
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines the structured data formats fields can be extracted from.
+pub enum DataKind {
+    Json,
+    JsonLines,
+    Yaml,
+    Csv,
+    Tsv
+}
+
+impl DataKind {
+    /// Identifies the data format from the file extension, returning `None` for unknown extensions.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" | "geojson" => Some(Self::Json),
+            "jsonl" | "ndjson" => Some(Self::JsonLines),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "csv" => Some(Self::Csv),
+            "tsv" | "tab" => Some(Self::Tsv),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Selects the field whose values are counted in structured data input.
+pub enum DataField {
+    JsonPath(JsonPath), // Values matching a JSONPath in JSON, JSON Lines or YAML input
+    CsvColumn(String), // Values of a column in CSV or TSV input, by header name or 1-based position
+}
+
+/// Converts a parse error into an `io::Error` reporting invalid input data.
+fn invalid_data(error: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+/// Calls `f` with the name and text of each value of the selected field. Values are named by their concrete JSONPath, prefixed with
+/// the line number for JSON Lines and YAML streams with several documents, or by the line number and column for CSV.
+/// JSON Lines and CSV input is parsed one record at a time rather than into a single tree, but the text is already in memory and callers like
+/// the frequency counts keep every value, so memory use still grows with the size of the input.
+/// Values selected from the wrong kind of data, like a column of JSON input, are an error.
+pub fn for_each_record(text: &str, kind: DataKind, field: &DataField, mut f: impl FnMut(String, String)) -> io::Result<()> {
+    match (kind, field) {
+        (DataKind::Json, DataField::JsonPath(path)) => {
+            let root: Value = serde_json::from_str(text).map_err(invalid_data)?;
+            path.select(&root).into_iter().for_each(|(name, value)| f(name, value_text(value)));
+        },
+        (DataKind::JsonLines, DataField::JsonPath(path)) => {
+            for (i, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+                let record: Value = serde_json::from_str(line).map_err(|e| invalid_data(format!("line {}: {}", i + 1, e)))?;
+                path.select(&record).into_iter().for_each(|(name, value)| f(format!("{}:{}", i + 1, name), value_text(value)));
+            }
+        },
+        (DataKind::Yaml, DataField::JsonPath(path)) => {
+            let documents = serde_yaml::Deserializer::from_str(text)
+                .map(Value::deserialize)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(invalid_data)?;
+            let is_stream = documents.len() > 1;
+            for (i, document) in documents.iter().enumerate() {
+                for (name, value) in path.select(document) {
+                    f(if is_stream { format!("{}:{}", i + 1, name) } else { name }, value_text(value));
+                }
+            }
+        },
+        (DataKind::Csv | DataKind::Tsv, DataField::CsvColumn(column)) => {
+            let mut reader = csv::ReaderBuilder::new()
+                .delimiter(if kind == DataKind::Tsv { b'\t' } else { b',' })
+                .flexible(true)
+                .from_reader(text.as_bytes());
+            let headers = reader.headers().map_err(invalid_data)?;
+            let index = headers.iter().position(|header| header.trim() == column)
+                .or_else(|| column.parse::<usize>().ok().filter(|i| *i > 0).map(|i| i - 1))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("no column named {column:?}")))?;
+            let mut record = csv::StringRecord::new();
+            while reader.read_record(&mut record).map_err(invalid_data)? {
+                let line = record.position().map_or(0, |p| p.line());
+                f(format!("{line}:{column}"), record.get(index).unwrap_or_default().to_string());
+            }
+        },
+        (DataKind::Csv | DataKind::Tsv, DataField::JsonPath(_)) => return Err(io::Error::new(io::ErrorKind::InvalidInput, "JSONPath requires JSON, JSON Lines or YAML input")),
+        (_, DataField::CsvColumn(_)) => return Err(io::Error::new(io::ErrorKind::InvalidInput, "columns require CSV or TSV input")),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rejects_unterminated_quoted_keys() {
+        assert!(JsonPath::parse("$[ ']").is_err());
+        assert!(JsonPath::parse("$['a").is_err());
+        assert!(JsonPath::parse("$[ 'a]").is_err());
+    }
+
+    #[test]
+    fn parse_accepts_empty_keys_and_wildcards() {
+        assert_eq!(JsonPath::parse("$[\"\"]").unwrap().segments, vec![Segment::Key(String::new())]);
+        assert_eq!(JsonPath::parse("$[*]").unwrap().segments, vec![Segment::Wildcard]);
+        assert_eq!(JsonPath::parse("$[ 'a b' ]").unwrap().segments, vec![Segment::Key("a b".to_string())]);
+    }
+}
@@ -0,0 +1,199 @@
+use std::path::Path;
+
+use crate::markup::{self, MarkupKind};
+use crate::{Table, TableStyle};
+
+/// Default speaking rate above which a cue is flagged as too fast to read comfortably, in words per minute.
+pub const DEFAULT_MAX_WPM: f64 = 180.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines the supported subtitle formats.
+pub enum SubtitleKind {
+    Srt,
+    Vtt
+}
+
+impl SubtitleKind {
+    /// Identifies the subtitle format from the `WEBVTT` header, falling back to the file extension and then to SubRip.
+    pub fn detect(path: &Path, text: &str) -> Self {
+        let is_vtt_extension = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("vtt"));
+        if text.trim_start_matches('\u{feff}').starts_with("WEBVTT") || is_vtt_extension { Self::Vtt } else { Self::Srt }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A single subtitle cue with its timing in milliseconds and spoken text.
+pub struct Cue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String, // Spoken text with formatting tags removed, keeping one line per subtitle line
+}
+
+impl Cue {
+    /// Returns the number of words in the cue, ignoring tokens without letters or digits like dialogue dashes.
+    pub fn words(&self) -> usize {
+        self.text.split_whitespace().filter(|w| w.chars().any(char::is_alphanumeric)).count()
+    }
+    /// Returns how long the cue is displayed in milliseconds.
+    pub fn duration_ms(&self) -> u64 {
+        self.end_ms.saturating_sub(self.start_ms)
+    }
+    /// Returns the speaking rate of the cue in words per minute, or zero for cues without a duration.
+    pub fn wpm(&self) -> f64 {
+        words_per_minute(self.words(), self.duration_ms())
+    }
+}
+
+/// Returns the rate of `words` spoken over `duration_ms` in words per minute, or zero when the duration is zero.
+fn words_per_minute(words: usize, duration_ms: u64) -> f64 {
+    if duration_ms == 0 { 0.0 } else { words as f64 * 60_000.0 / duration_ms as f64 }
+}
+
+/// Parses a timestamp like `01:02:03,456` (SubRip) or `02:03.456` (WebVTT, where hours are optional) into milliseconds.
+fn parse_timestamp(timestamp: &str) -> Option<u64> {
+    let (clock, fraction) = timestamp.split_once([',', '.'])?;
+    let millis: u64 = fraction.get(..3).filter(|f| f.len() == 3)?.parse().ok()?;
+    let parts = clock.split(':').map(|p| p.parse::<u64>().ok()).collect::<Option<Vec<_>>>()?;
+    let (hours, minutes, seconds) = match parts[..] {
+        [hours, minutes, seconds] => (hours, minutes, seconds),
+        [minutes, seconds] => (0, minutes, seconds),
+        _ => return None,
+    };
+    Some(((hours * 60 + minutes) * 60 + seconds) * 1000 + millis)
+}
+
+/// Formats milliseconds as `h:mm:ss.mmm`.
+pub fn fmt_timestamp(ms: u64) -> String {
+    format!("{}:{:02}:{:02}.{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+}
+
+/// Removes inline markup from a line of cue text: HTML-like tags such as `<i>` or `<v Speaker>`, WebVTT timestamp tags and
+/// SubStation override blocks like `{\an8}` that some SubRip files carry, then decodes character references.
+fn strip_cue_markup(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(i) = rest.find(['<', '{']) {
+        text.push_str(&rest[..i]);
+        let close = if rest[i..].starts_with('<') { '>' } else { '}' };
+        // Braces not opening an override block are kept as text
+        if close == '}' && !rest[i + 1..].starts_with('\\') {
+            text.push('{');
+            rest = &rest[i + 1..];
+            continue;
+        }
+        match rest[i..].find(close) {
+            Some(end) => rest = &rest[i + end + 1..],
+            None => {
+                rest = &rest[i..];
+                break;
+            },
+        }
+    }
+    text.push_str(rest);
+    markup::decode_entities(&text, MarkupKind::Html).into_owned()
+}
+
+/// Parses the cues of a SubRip or WebVTT file in order, dropping cue numbers, identifiers, timestamps and cue settings.
+/// WebVTT header, `NOTE`, `STYLE` and `REGION` blocks are skipped, as are blocks without a valid timing line.
+pub fn parse(text: &str, kind: SubtitleKind) -> Vec<Cue> {
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n").replace('\r', "\n");
+    let mut cues = Vec::new();
+    let mut lines = text.lines().peekable();
+    while lines.peek().is_some() {
+        // Blocks are separated by one or more blank lines
+        let block: Vec<&str> = lines.by_ref().skip_while(|l| l.trim().is_empty()).take_while(|l| !l.trim().is_empty()).collect();
+        let Some(timing_index) = block.iter().position(|l| l.contains("-->")) else {
+            continue;
+        };
+        if kind == SubtitleKind::Vtt && block.first().is_some_and(|l| ["WEBVTT", "NOTE", "STYLE", "REGION"].iter().any(|b| l.starts_with(b))) {
+            continue;
+        }
+        let (start, end) = block[timing_index].split_once("-->").unwrap_or_default();
+        // WebVTT cue settings and SubRip coordinates follow the end timestamp
+        let timing = (parse_timestamp(start.trim()), end.split_whitespace().next().and_then(parse_timestamp));
+        let (Some(start_ms), Some(end_ms)) = timing else {
+            continue;
+        };
+        let text = block[timing_index + 1..].iter().map(|l| strip_cue_markup(l)).collect::<Vec<_>>().join("\n");
+        cues.push(Cue { start_ms, end_ms, text });
+    }
+    cues
+}
+
+/// Summarizes the timing of the cues: the duration from the first cue to the end of the last, the time cues are shown for,
+/// the overall speaking rate in words per minute over the time cues are shown and the number of cues faster than `max_wpm`.
+pub fn summarize(cues: &[Cue], max_wpm: f64) -> String {
+    let duration_ms = cues.iter().map(|c| c.end_ms).max().unwrap_or_default().saturating_sub(cues.iter().map(|c| c.start_ms).min().unwrap_or_default());
+    let speaking_ms: u64 = cues.iter().map(Cue::duration_ms).sum();
+    let words: usize = cues.iter().map(Cue::words).sum();
+    let fast = cues.iter().filter(|c| c.wpm() > max_wpm).count();
+    format!("{} cues, {} duration, {} spoken, {:.0} words per minute, {} cues above {:.0} words per minute\n",
+        cues.len(), fmt_timestamp(duration_ms), fmt_timestamp(speaking_ms), words_per_minute(words, speaking_ms), fast, max_wpm)
+}
+
+/// Create a table of the timing, word count and speaking rate of each cue, marking cues faster than `max_wpm` words per minute.
+pub fn generate_cue_table(cues: &[Cue], max_wpm: f64, style: TableStyle) -> Table<String, String> {
+    let headers = vec!["cue", "start", "end", "words", "wpm", "fast"].into_iter().map(String::from).collect();
+    let data = cues.iter().enumerate()
+        .map(|(i, cue)| {
+            let wpm = cue.wpm();
+            let fmt_fast = if wpm > max_wpm { "!".to_string() } else { String::new() };
+            vec![(i + 1).to_string(), fmt_timestamp(cue.start_ms), fmt_timestamp(cue.end_ms), cue.words().to_string(), format!("{wpm:.0}"), fmt_fast]
+        })
+        .collect();
+    Table::new_with_style(headers, data, style, ">>>>><")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRT: &str = "1\r\n00:00:01,000 --> 00:00:03,000\r\n<i>Hello there</i>, friend\r\n\r\n2\r\n00:00:03,500 --> 00:00:04,500 X1:0\r\n- Run! {\\an8}Now &amp; fast\r\n\r\n3\r\nbad timing\r\ntext\r\n";
+
+    const VTT: &str = "\u{feff}WEBVTT - title
+
+NOTE a comment
+with --> arrow
+
+STYLE
+::cue { color: red }
+
+intro
+00:01.000 --> 00:04.000 align:start
+<v Alice>Hi <00:02.000>there Bob
+
+01:00:00.000 --> 01:00:30.000
+Long cue
+";
+
+    /// Returns the timing, text and words per minute of each cue.
+    fn cue_rows(cues: &[Cue]) -> Vec<(u64, u64, &str, f64)> {
+        cues.iter().map(|cue| (cue.start_ms, cue.end_ms, cue.text.as_str(), cue.wpm())).collect()
+    }
+
+    #[test]
+    fn parses_srt_cue_timing_and_rate() {
+        let cues = parse(SRT, SubtitleKind::Srt);
+        assert_eq!(cue_rows(&cues), [(1000, 3000, "Hello there, friend", 90.0), (3500, 4500, "- Run! Now & fast", 180.0)]);
+        // Dialogue dashes and symbols are not words
+        assert_eq!(cues[1].words(), 3);
+        assert_eq!(summarize(&cues, 150.0), "2 cues, 0:00:03.500 duration, 0:00:03.000 spoken, 120 words per minute, 1 cues above 150 words per minute\n");
+    }
+
+    #[test]
+    fn parses_webvtt_skipping_header_and_note_blocks() {
+        let cues = parse(VTT, SubtitleKind::detect(Path::new("talk.txt"), VTT));
+        assert_eq!(cue_rows(&cues), [(1000, 4000, "Hi there Bob", 60.0), (3_600_000, 3_630_000, "Long cue", 4.0)]);
+    }
+
+    #[test]
+    fn detects_kind_and_parses_timestamps() {
+        assert_eq!(SubtitleKind::detect(Path::new("a.VTT"), ""), SubtitleKind::Vtt);
+        assert_eq!(SubtitleKind::detect(Path::new("a.srt"), SRT), SubtitleKind::Srt);
+        assert_eq!(parse_timestamp("1:02:03,004"), Some(3_723_004));
+        assert_eq!(parse_timestamp("02:03.456"), Some(123_456));
+        assert_eq!([parse_timestamp("1:2"), parse_timestamp("00:01.5"), parse_timestamp("1:2:3:4.000")], [None; 3]);
+        assert_eq!(fmt_timestamp(3_723_004), "1:02:03.004");
+        assert_eq!(Cue { start_ms: 5, end_ms: 5, text: "word".to_string() }.wpm(), 0.0);
+    }
+}
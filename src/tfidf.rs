@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use rustc_hash::FxBuildHasher;
+
+use crate::{Table, TableStyle, WordMap};
+
+/// Maps each term to the number of documents it occurs in, hashed with FxHash like `WordMap`.
+pub type DocumentFrequencies<'a> = HashMap<&'a str, usize, FxBuildHasher>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines how TF-IDF rankings are rendered when multiple files are provided.
+pub enum TfIdfLayout {
+    PerFile, // One table per file
+    Combined // One long-form table with a column naming the file
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Weighting and smoothing options for computing TF-IDF scores.
+pub struct TfIdfOptions {
+    pub is_smooth_idf: bool, // Use `ln((1 + N) / (1 + df)) + 1` so terms present in every file keep a non-zero weight
+    pub is_sublinear_tf: bool, // Use `1 + ln(ct)` instead of the relative frequency to dampen very frequent terms
+}
+
+#[derive(Debug, Clone)]
+/// A term in a single document along with its raw count and TF-IDF score.
+pub struct TermScore<'a> {
+    pub term: &'a str,
+    pub count: usize,
+    pub score: f64,
+}
+
+/// Returns the number of documents each term occurs in.
+pub fn document_frequencies<'a>(documents: &'a [WordMap]) -> DocumentFrequencies<'a> {
+    let mut doc_freqs = DocumentFrequencies::default();
+    for word_map in documents {
+        for term in word_map.keys() {
+            *doc_freqs.entry(term.as_ref()).or_insert(0) += 1;
+        }
+    }
+    doc_freqs
+}
+
+/// Returns the inverse document frequency of a term occurring in `doc_freq` of `n_docs` documents.
+pub fn inverse_document_frequency(doc_freq: usize, n_docs: usize, is_smooth: bool) -> f64 {
+    if is_smooth {
+        ((1 + n_docs) as f64 / (1 + doc_freq) as f64).ln() + 1.0
+    } else if doc_freq == 0 {
+        0.0
+    } else {
+        (n_docs as f64 / doc_freq as f64).ln()
+    }
+}
+
+/// Scores every term in `word_map` passing `is_counted` against the document frequencies of the whole set, sorted by descending score with ties broken alphabetically.
+pub fn rank_terms<'a>(word_map: &'a WordMap, doc_freqs: &DocumentFrequencies, n_docs: usize, options: TfIdfOptions, is_counted: impl Fn(&str) -> bool) -> Vec<TermScore<'a>> {
+    let total: usize = word_map.values().sum();
+    let mut scores: Vec<TermScore> = word_map.iter()
+        .filter(|(term, _)| is_counted(term))
+        .map(|(term, count)| {
+            let tf = if options.is_sublinear_tf { 1.0 + (*count as f64).ln() } else { *count as f64 / total as f64 };
+            let idf = inverse_document_frequency(doc_freqs.get(term.as_ref()).copied().unwrap_or(0), n_docs, options.is_smooth_idf);
+            TermScore { term, count: *count, score: tf * idf }
+        })
+        .collect();
+    scores.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.term.cmp(b.term)));
+    scores
+}
+
+/// Create a table of the top `top_n` terms for a single document.
+pub fn generate_tfidf_table(scores: &[TermScore], top_n: usize, style: TableStyle) -> Table<String, String> {
+    let headers = vec!["#", "term", "ct", "tf-idf"].into_iter().map(String::from).collect();
+    let data = scores.iter().take(top_n).enumerate()
+        .map(|(i, s)| vec![(i + 1).to_string(), s.term.to_string(), s.count.to_string(), format!("{:.4}", s.score)])
+        .collect();
+    Table::new_with_style(headers, data, style, "><>>")
+}
+
+/// Create a single long-form table of the top `top_n` terms for each named document.
+pub fn generate_combined_table(rankings: &[(String, Vec<TermScore>)], top_n: usize, style: TableStyle) -> Table<String, String> {
+    let headers = vec!["file", "#", "term", "ct", "tf-idf"].into_iter().map(String::from).collect();
+    let data = rankings.iter()
+        .flat_map(|(name, scores)| scores.iter().take(top_n).enumerate()
+            .map(move |(i, s)| vec![name.clone(), (i + 1).to_string(), s.term.to_string(), s.count.to_string(), format!("{:.4}", s.score)]))
+        .collect();
+    Table::new_with_style(headers, data, style, "<><>>")
+}
@@ -0,0 +1,76 @@
+use std::io::{self, Write};
+
+use crate::{Table, TableStyle, WordMap};
+
+#[derive(Debug, Clone)]
+/// Least squares fit of the rank-frequency curve in log-log space, where Zipf's law predicts `freq ∝ rank^-exponent` with an exponent near 1.
+pub struct ZipfFit {
+    pub exponent: f64, // Negated slope of the fitted line, ~1.0 for natural language text
+    pub intercept: f64, // Natural log of the predicted frequency at rank 1
+    pub r_squared: f64, // Coefficient of determination of the fit in log-log space
+    pub n_ranks: usize, // Number of ranks (distinct words) used for the fit
+}
+
+impl ZipfFit {
+    /// Fit the curve to frequencies sorted in descending order, returning `None` if there are fewer than two ranks to fit.
+    pub fn fit(frequencies: &[usize]) -> Option<Self> {
+        let n_ranks = frequencies.len();
+        if n_ranks < 2 {
+            return None
+        }
+        let points: Vec<(f64, f64)> = frequencies.iter().enumerate().map(|(i, f)| (((i + 1) as f64).ln(), (*f as f64).ln())).collect();
+        let n = n_ranks as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let ss_xy: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        let ss_xx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        let ss_yy: f64 = points.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+        let slope = ss_xy / ss_xx;
+        let intercept = mean_y - slope * mean_x;
+        // A perfectly flat distribution has no variance to explain so treat it as a perfect (if uninteresting) fit
+        let r_squared = if ss_yy > 0.0 { (ss_xy * ss_xy) / (ss_xx * ss_yy) } else { 1.0 };
+        Some(ZipfFit { exponent: -slope, intercept, r_squared, n_ranks })
+    }
+    /// Returns the frequency predicted by the fit for the provided 1-based rank.
+    pub fn predicted(&self, rank: usize) -> f64 {
+        (self.intercept - self.exponent * (rank as f64).ln()).exp()
+    }
+    /// Returns the relative deviation of an observed frequency from the predicted frequency at `rank`, positive values are over-represented.
+    pub fn deviation(&self, rank: usize, observed: usize) -> f64 {
+        let predicted = self.predicted(rank);
+        (observed as f64 - predicted) / predicted
+    }
+}
+
+/// Returns the words and their frequencies sorted by descending frequency, breaking ties alphabetically so ranks are stable between runs.
+pub fn rank_frequency<'a>(word_map: &'a WordMap) -> Vec<(&'a str, usize)> {
+    let mut ranked: Vec<(&str, usize)> = word_map.iter().map(|(w, c)| (w.as_ref(), *c)).collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    ranked
+}
+
+/// Create a table comparing observed and predicted frequencies for the top `top_n` ranks.
+pub fn generate_deviation_table(fit: &ZipfFit, ranked: &[(&str, usize)], top_n: usize, style: TableStyle) -> Table<String, String> {
+    let headers = vec!["rank", "word", "ct", "expected", "dev"].into_iter().map(String::from).collect();
+    let data = ranked.iter().take(top_n).enumerate()
+        .map(|(i, (word, count))| {
+            let rank = i + 1;
+            vec![rank.to_string(), word.to_string(), count.to_string(), format!("{:.1}", fit.predicted(rank)), format!("{:+.1}%", fit.deviation(rank, *count) * 100.0)]
+        })
+        .collect();
+    Table::new_with_style(headers, data, style, "><>>>")
+}
+
+/// Write the rank and frequency pairs as CSV with a header row for plotting in external tools.
+pub fn export_rank_frequency<W: Write>(writer: &mut W, ranked: &[(&str, usize)]) -> io::Result<()> {
+    writeln!(writer, "rank,word,frequency")?;
+    for (i, (word, count)) in ranked.iter().enumerate() {
+        // Quote words containing delimiters or quotes, which can only occur when all characters are included
+        if word.contains([',', '"']) {
+            writeln!(writer, "{},\"{}\",{}", i + 1, word.replace('"', "\"\""), count)?;
+        } else {
+            writeln!(writer, "{},{},{}", i + 1, word, count)?;
+        }
+    }
+    Ok(())
+}